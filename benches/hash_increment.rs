@@ -0,0 +1,40 @@
+//! Compare the cost of `hash_increment`, which reduces hashes with a
+//! multiply-shift, for power-of-two and other sizes, against reducing with `%`.
+//!
+//! Run with `cargo bench --features hash_increment`.
+
+use sancov::Counters;
+use std::hint::black_box;
+use std::time::Instant;
+
+const KEYS: u64 = 1 << 22;
+
+static POW2: Counters<65536> = Counters::new();
+static PRIME: Counters<65521> = Counters::new();
+
+fn bench(name: &str, mut f: impl FnMut(u64)) {
+    for key in 0..KEYS / 16 {
+        f(black_box(key));
+    }
+    let start = Instant::now();
+    for key in 0..KEYS {
+        f(black_box(key));
+    }
+    let ns = start.elapsed().as_nanos() as f64 / KEYS as f64;
+    println!("{name:<44} {ns:>6.2} ns/iter");
+}
+
+fn main() {
+    bench("hash_increment, N = 65536", |key| POW2.hash_increment(&key));
+    bench("hash_increment, N = 65521", |key| {
+        PRIME.hash_increment(&key)
+    });
+    bench("fxhash64 % N, N = 65536", |key| {
+        let i = (fxhash::hash64(&key) % 65536) as usize;
+        POW2[i].increment();
+    });
+    bench("fxhash64 % N, N = 65521", |key| {
+        let i = (fxhash::hash64(&key) % 65521) as usize;
+        PRIME[i].increment();
+    });
+}
@@ -0,0 +1,37 @@
+/*
+ * C declarations for the functions exported by the `sancov` crate's `ffi`
+ * feature.
+ *
+ * These must match the `extern "C"` definitions in `src/ffi.rs`, which is
+ * checked by the crate's tests. See the Rust documentation of each function
+ * for its exact semantics and safety requirements.
+ */
+
+#ifndef SANCOV_H
+#define SANCOV_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Increment the counter at `ptr`, with "NeverZero" semantics. */
+void sancov_counter_increment(uint8_t *ptr);
+
+/* Increment the counter selected by `hash` within the `len` counters starting
+ * at `base`, using the same reduction as `Counters::hash_increment_raw`, i.e.
+ * `(hash * len) >> 64` computed in 128 bits. Does nothing if `len` is zero. */
+void sancov_hash_increment(uint8_t *base, size_t len, uint64_t hash);
+
+/* Increment the counters at each of the `count` indices starting at
+ * `indices`, within the `len` counters starting at `base`. Indices that are
+ * `>= len` are ignored. */
+void sancov_increment_indices(uint8_t *base, size_t len, const uint32_t *indices, size_t count);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SANCOV_H */
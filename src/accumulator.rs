@@ -0,0 +1,147 @@
+use crate::Counters;
+
+/// The coverage seen across many runs of a set of `N` counters.
+///
+/// An `Accumulator<N>` folds successive observations of a
+/// [`Counters<N>`][crate::Counters] into a single map, and reports whether each
+/// observation contributed any bits that had not been seen before. This is the
+/// feedback check at the heart of most coverage-guided fuzzers: an input is
+/// interesting if, and only if, observing its counters returns `true`.
+///
+/// A raw accumulator, created with [`new`][crate::Accumulator::new], compares
+/// counter values bit by bit. A classified accumulator, created with
+/// [`classified`][crate::Accumulator::classified], first collapses each counter
+/// into a bucket with [`COUNT_CLASS_LOOKUP`][crate::COUNT_CLASS_LOOKUP], so that
+/// small changes in hit counts are not reported as new coverage.
+///
+/// # Example
+///
+/// ```
+/// use sancov::{Accumulator, Counters};
+///
+/// let counters = Counters::<64>::new();
+/// let mut seen = Accumulator::<64>::classified();
+///
+/// counters[3].increment();
+/// assert!(seen.observe(&counters));
+///
+/// counters.reset();
+/// counters[3].increment();
+/// assert!(!seen.observe(&counters));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Accumulator<const N: usize> {
+    virgin: [u8; N],
+    classify: bool,
+}
+
+impl<const N: usize> Accumulator<N> {
+    /// Construct an accumulator that has seen nothing, and compares raw
+    /// counter values.
+    ///
+    /// # Compile-time errors
+    ///
+    /// `N` must not be zero.
+    pub const fn new() -> Self {
+        const { assert!(N != 0, "`N` must not be zero") };
+        Accumulator {
+            virgin: [0xff; N],
+            classify: false,
+        }
+    }
+
+    /// Construct an accumulator that has seen nothing, and classifies counter
+    /// values into buckets before comparing them.
+    ///
+    /// # Compile-time errors
+    ///
+    /// `N` must not be zero.
+    pub const fn classified() -> Self {
+        let mut accumulator = Self::new();
+        accumulator.classify = true;
+        accumulator
+    }
+
+    /// Fold `current` into the accumulated map, returning whether it contained
+    /// any bits that had not been seen before.
+    pub fn observe(&mut self, current: &Counters<N>) -> bool {
+        if self.classify {
+            current.update_virgin(&mut self.virgin) != 0
+        } else {
+            current.has_new_bits(&mut self.virgin)
+        }
+    }
+
+    /// Whether this accumulator classifies counter values before comparing
+    /// them.
+    pub fn is_classified(&self) -> bool {
+        self.classify
+    }
+
+    /// The number of counters for which any bits have been seen.
+    pub fn count_seen(&self) -> usize {
+        self.virgin.iter().filter(|&&v| v != 0xff).count()
+    }
+
+    /// The accumulated map, in the AFL-style "virgin" form accepted by
+    /// [`has_new_bits`][crate::Counters::has_new_bits]: a bit is set if it has
+    /// *not* yet been seen.
+    pub fn virgin_map(&self) -> &[u8; N] {
+        &self.virgin
+    }
+
+    /// Forget everything that has been seen.
+    pub fn reset(&mut self) {
+        self.virgin = [0xff; N];
+    }
+}
+
+impl<const N: usize> Default for Accumulator<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, not(sancov_disabled)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_observation_is_all_new() {
+        let counters = Counters::<19>::new();
+        for i in 0..19 {
+            counters[i].increment_by(i as u8 + 1);
+        }
+        let mut raw = Accumulator::<19>::new();
+        assert!(raw.observe(&counters));
+        assert_eq!(raw.count_seen(), 19);
+        let mut classified = Accumulator::<19>::classified();
+        assert!(classified.observe(&counters));
+        assert_eq!(classified.count_seen(), 19);
+    }
+
+    #[test]
+    fn no_new_coverage() {
+        let counters = Counters::<19>::new();
+        let mut raw = Accumulator::<19>::new();
+        let mut classified = Accumulator::<19>::classified();
+        assert!(!raw.observe(&counters));
+        assert!(!classified.observe(&counters));
+
+        counters[2].increment_by(5);
+        counters[17].increment();
+        assert!(raw.observe(&counters));
+        assert!(classified.observe(&counters));
+        assert!(!raw.observe(&counters));
+        assert!(!classified.observe(&counters));
+
+        // 6 and 7 share a bucket with 5, but differ in their raw bits.
+        counters.reset();
+        counters[2].increment_by(6);
+        assert!(raw.observe(&counters));
+        assert!(!classified.observe(&counters));
+
+        classified.reset();
+        assert!(classified.observe(&counters));
+    }
+}
@@ -0,0 +1,77 @@
+//! The byte-sized atomics that counters and flags are built on.
+//!
+//! On targets without atomic byte operations (`target_has_atomic = "8"` is
+//! false), such as some microcontrollers, these are replaced by non-atomic
+//! stand-ins with the same layout and interface, so that the crate still builds
+//! there. The stand-ins use plain reads and writes, and are only sound to use
+//! from a single thread.
+
+pub(crate) use core::sync::atomic::Ordering;
+
+#[cfg(target_has_atomic = "8")]
+pub(crate) use core::sync::atomic::{AtomicBool, AtomicU8};
+
+#[cfg(not(target_has_atomic = "8"))]
+pub(crate) use fallback::{AtomicBool, AtomicU8};
+
+#[cfg(not(target_has_atomic = "8"))]
+mod fallback {
+    use super::Ordering;
+    use core::cell::UnsafeCell;
+
+    macro_rules! fallback_atomics {
+        ($($name:ident: $ty:ty;)*) => {
+            $(
+                #[repr(transparent)]
+                pub(crate) struct $name(UnsafeCell<$ty>);
+
+                // Safety: not actually! Without atomic byte operations, there
+                // is no way to share these between threads soundly, but statics
+                // must be `Sync`. The crate documents that on such targets,
+                // counters must only be used from a single thread.
+                unsafe impl Sync for $name {}
+
+                impl $name {
+                    #[inline]
+                    #[allow(dead_code)]
+                    pub(crate) const fn new(v: $ty) -> Self {
+                        $name(UnsafeCell::new(v))
+                    }
+
+                    #[inline]
+                    pub(crate) fn load(&self, _: Ordering) -> $ty {
+                        unsafe { self.0.get().read_volatile() }
+                    }
+
+                    #[inline]
+                    pub(crate) fn store(&self, v: $ty, _: Ordering) {
+                        unsafe { self.0.get().write_volatile(v) }
+                    }
+
+                    #[inline]
+                    #[allow(dead_code)]
+                    pub(crate) fn compare_exchange(
+                        &self,
+                        current: $ty,
+                        new: $ty,
+                        success: Ordering,
+                        _: Ordering,
+                    ) -> Result<$ty, $ty> {
+                        let v = self.load(success);
+                        if v == current {
+                            self.store(new, success);
+                            Ok(v)
+                        } else {
+                            Err(v)
+                        }
+                    }
+                }
+            )*
+        };
+    }
+
+    fallback_atomics! {
+        AtomicU8: u8;
+        AtomicBool: bool;
+    }
+}
@@ -0,0 +1,137 @@
+#[cfg_attr(sancov_disabled, allow(unused_imports))]
+use crate::sys;
+use core::cell::{Cell, UnsafeCell};
+use core::ops::Index;
+
+/// A collection of `N` non-atomic counters, for single-threaded programs.
+///
+/// This is like [`Counters<N>`][crate::Counters], but its counters are
+/// incremented with plain, non-atomic loads and stores, matching the counters
+/// that clang emits for `-fsanitize-coverage=inline-8bit-counters`. This lets
+/// the compiler optimize increments more aggressively.
+///
+/// `CountersCell<N>` is not `Sync`, so it cannot be put in a `static`. Since
+/// registration requires a `'static` reference, leak it instead.
+///
+/// `CountersCell<N>` has the same representation as `[u8; N]`.
+///
+/// # Example
+///
+/// ```
+/// use sancov::CountersCell;
+///
+/// // Define and leak some counters.
+/// let counters: &'static CountersCell<4096> = Box::leak(Box::new(CountersCell::new()));
+///
+/// // Register the counters with the `SanitizerCoverage` consumer.
+/// counters.register();
+///
+/// // Increment a counter.
+/// counters[42].increment();
+/// assert_eq!(counters[42].get(), 1);
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+/// ```
+#[repr(transparent)]
+pub struct CountersCell<const N: usize>(UnsafeCell<[u8; N]>);
+
+impl<const N: usize> CountersCell<N> {
+    /// Construct a new set of `N` counters.
+    ///
+    /// # Compile-time errors
+    ///
+    /// `N` must not be zero.
+    pub const fn new() -> Self {
+        const { assert!(N != 0, "`N` must not be zero") };
+        CountersCell(UnsafeCell::new([0; N]))
+    }
+
+    /// Get the underlying array of counters.
+    #[inline]
+    pub fn as_array(&self) -> &[CounterCell; N] {
+        unsafe {
+            let ptr: *mut [u8; N] = self.0.get();
+            let ptr: *const [CounterCell; N] = ptr as _;
+            &*ptr
+        }
+    }
+
+    /// Register the given counters with the `SanitizerCoverage` consumer.
+    ///
+    /// The `SanitizerCoverage` API does not provide any method of unregistering
+    /// counters, so `&self` must be `'static`.
+    ///
+    /// With the `register_once` feature, the `SanitizerCoverage` consumer is
+    /// only called once for these counters; see
+    /// [`Counters::register`][crate::Counters::register].
+    ///
+    /// With the `std` feature, the counters are also recorded in the
+    /// process-wide registry, so they appear in `sancov::registered_regions`,
+    /// but `sancov::reset_all` does not reset them.
+    pub fn register(&'static self) {
+        enabled! {
+            let start = self.0.get() as *const u8;
+            #[cfg(feature = "register_once")]
+            unsafe {
+                crate::registry::record_once(start, N, false, sys::__sanitizer_cov_8bit_counters_init);
+            }
+            #[cfg(not(feature = "register_once"))]
+            unsafe {
+                sys::__sanitizer_cov_8bit_counters_init(start, start.add(N));
+                #[cfg(feature = "std")]
+                crate::registry::record(start, N, false);
+            }
+        }
+    }
+}
+
+impl<const N: usize> Default for CountersCell<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Index<usize> for CountersCell<N> {
+    type Output = CounterCell;
+
+    #[inline]
+    fn index(&self, index: usize) -> &Self::Output {
+        assert!(index < N);
+        &self.as_array()[index]
+    }
+}
+
+/// A single non-atomic 8-bit counter.
+///
+/// It has the same representation as a `u8`.
+#[repr(transparent)]
+pub struct CounterCell(Cell<u8>);
+
+impl CounterCell {
+    /// Increment this counter.
+    ///
+    /// This uses the same "NeverZero" approach as
+    /// [`Counter::increment`][crate::Counter::increment].
+    #[inline]
+    pub fn increment(&self) {
+        enabled! {
+            self.0.set(crate::neverzero_next(self.0.get()));
+        }
+    }
+
+    /// Increment this counter, saturating at `u8::MAX`.
+    #[inline]
+    pub fn saturating_increment(&self) {
+        enabled! {
+            self.0.set(self.0.get().saturating_add(1));
+        }
+    }
+
+    /// Get this counter's current value.
+    #[inline]
+    pub fn get(&self) -> u8 {
+        self.0.get()
+    }
+}
@@ -0,0 +1,100 @@
+use crate::atomic::{AtomicU8, Ordering};
+use crate::{neverzero_next, Counters};
+use core::sync::atomic::AtomicU64;
+
+/// The number of times that an increment's compare-and-swap has failed, across
+/// the whole process.
+static RETRIES: AtomicU64 = AtomicU64::new(0);
+
+/// Increment `counter` without losing concurrent increments, with a
+/// compare-and-swap loop that counts every retry.
+#[inline]
+#[cfg_attr(sancov_disabled, allow(dead_code))]
+pub(crate) fn increment(counter: &AtomicU8) {
+    increment_from(counter, counter.load(Ordering::Relaxed), &RETRIES);
+}
+
+/// Increment `counter`, which was last seen holding `count`, adding every
+/// retry to `retries`.
+#[inline]
+#[cfg_attr(sancov_disabled, allow(dead_code))]
+fn increment_from(counter: &AtomicU8, mut count: u8, retries: &AtomicU64) {
+    while let Err(actual) = counter.compare_exchange(
+        count,
+        neverzero_next(count),
+        Ordering::Relaxed,
+        Ordering::Relaxed,
+    ) {
+        retries.fetch_add(1, Ordering::Relaxed);
+        count = actual;
+    }
+}
+
+impl<const N: usize> Counters<N> {
+    /// Estimate how often increments have contended with each other.
+    ///
+    /// This is the number of times that
+    /// [`Counter::increment`][crate::Counter::increment] had to retry because
+    /// another thread changed the counter between its load and its store. With
+    /// the ordinary load-then-store increment, each of those could have been a
+    /// lost increment, so a high number suggests that threads should
+    /// increment their own counters and fold them together, e.g. with
+    /// `ThreadLocalCounters`.
+    ///
+    /// The count cannot be stored next to the counters without changing their
+    /// layout, so it is shared by the whole process: it covers increments of
+    /// every set of counters, not just `self`. It only ever grows.
+    ///
+    /// Requires the `contention_stats` feature, which is not purely
+    /// diagnostic: it makes every `Counter::increment` a lossless
+    /// compare-and-swap loop that counts its retries, instead of a relaxed
+    /// load and store. That is slower, and under contention it records
+    /// increments that the ordinary increment would have lost, so the
+    /// counters themselves can differ from a build without the feature. The
+    /// estimate is therefore of how much contention there would be, not a
+    /// measurement of increments that were actually lost.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<1> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// std::thread::scope(|s| {
+    ///     for _ in 0..4 {
+    ///         s.spawn(|| {
+    ///             for _ in 0..1000 {
+    ///                 COUNTERS[0].increment();
+    ///             }
+    ///         });
+    ///     }
+    /// });
+    /// println!("{} contended increments", COUNTERS.contention_estimate());
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    pub fn contention_estimate(&self) -> u64 {
+        RETRIES.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_count_retries_exactly_once() {
+        let retries = AtomicU64::new(0);
+        let counter = AtomicU8::new(5);
+        increment_from(&counter, 3, &retries);
+        assert_eq!(counter.load(Ordering::Relaxed), 6);
+        assert_eq!(retries.load(Ordering::Relaxed), 1);
+
+        increment_from(&counter, 6, &retries);
+        assert_eq!(counter.load(Ordering::Relaxed), 7);
+        assert_eq!(retries.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,89 @@
+//! A C ABI for incrementing counters from foreign code, such as JIT-compiled
+//! code.
+//!
+//! These functions are exported with unmangled names, so that code generators
+//! can emit plain calls to them instead of reimplementing the increment logic.
+//! Their semantics are exactly those of the corresponding Rust methods, so
+//! counters incremented from foreign code and from Rust stay consistent.
+//!
+//! C and C++ declarations for these functions are in `include/sancov.h`, which
+//! ships with the crate.
+//!
+//! Requires the `ffi` feature.
+
+use crate::Counter;
+
+/// Increment the counter at `ptr`.
+///
+/// This is [`Counter::increment`][crate::Counter::increment]: a relaxed atomic
+/// load, an add of one where an overflow carry is folded back in so that the
+/// counter is never left at zero ("NeverZero", see
+/// [`neverzero_next`][crate::neverzero_next]), and a relaxed atomic store.
+///
+/// # Safety
+///
+/// `ptr` must be non-null, and valid for atomic reads and writes of one byte,
+/// for example a pointer into a registered [`Counters<N>`][crate::Counters].
+#[no_mangle]
+pub unsafe extern "C" fn sancov_counter_increment(ptr: *mut u8) {
+    let counter = &*(ptr as *const Counter);
+    counter.increment();
+}
+
+/// Increment the counter selected by `hash` within the `len` counters starting
+/// at `base`.
+///
+/// This is `Counters::hash_increment_raw`: `hash` is reduced to an index in
+/// `0..len` with the same multiply-shift as
+/// [`Counters::hash_increment`][crate::Counters::hash_increment], so the same
+/// hash selects the same counter from foreign code and from Rust. The
+/// increment is exactly that of [`sancov_counter_increment`]. If `len` is
+/// zero, this does nothing.
+///
+/// # Safety
+///
+/// If `len` is non-zero, `base` must be valid for atomic reads and writes of
+/// `len` bytes, for example the start of a registered
+/// [`Counters<N>`][crate::Counters] with `len == N`.
+#[no_mangle]
+pub unsafe extern "C" fn sancov_hash_increment(base: *mut u8, len: usize, hash: u64) {
+    if len == 0 {
+        return;
+    }
+    let i = crate::reduce(hash, len);
+    sancov_counter_increment(base.add(i));
+}
+
+/// Increment the counters at each of the `count` indices starting at
+/// `indices`, within the `len` counters starting at `base`.
+///
+/// This lets foreign code buffer a burst of edges and apply them in a single
+/// call. Each increment is exactly that of [`sancov_counter_increment`], and
+/// an index that appears more than once is incremented that many times.
+/// Indices that are `>= len` are ignored, so a stale or corrupt buffer cannot
+/// write out of bounds. If `count` is zero, `indices` is not read.
+///
+/// # Safety
+///
+/// If `len` is non-zero, `base` must be valid for atomic reads and writes of
+/// `len` bytes, for example the start of a registered
+/// [`Counters<N>`][crate::Counters] with `len == N`. If `count` is non-zero,
+/// `indices` must be valid for reads of `count` `u32`s.
+#[no_mangle]
+pub unsafe extern "C" fn sancov_increment_indices(
+    base: *mut u8,
+    len: usize,
+    indices: *const u32,
+    count: usize,
+) {
+    if count == 0 {
+        return;
+    }
+    let indices = core::slice::from_raw_parts(indices, count);
+    for &i in indices {
+        let i = i as usize;
+        if i < len {
+            sancov_counter_increment(base.add(i));
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use crate::Counters;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// A record of when each of `N` counters was first hit.
+///
+/// `FirstSeen<N>` is a parallel structure to a [`Counters<N>`][crate::Counters]
+/// that stores, for each counter, the epoch at which it first transitioned from
+/// zero. The epoch is supplied by the caller, so it can be anything that
+/// increases monotonically: an iteration number, the number of executions, or
+/// a timestamp from whatever clock is available. This keeps `FirstSeen` usable
+/// in `no_std` environments, and lets you plot coverage over time without any
+/// external bookkeeping.
+///
+/// Epochs are stored in `u64`s, with `u64::MAX` reserved; an epoch of
+/// `u64::MAX` is recorded as `u64::MAX - 1`.
+///
+/// # Example
+///
+/// ```
+/// use sancov::{Counters, FirstSeen};
+///
+/// static COUNTERS: Counters<4096> = Counters::new();
+/// static FIRST_SEEN: FirstSeen<4096> = FirstSeen::new();
+///
+/// COUNTERS.register();
+///
+/// for iteration in 0..10 {
+///     FIRST_SEEN.increment(&COUNTERS, 42, iteration);
+///     if iteration >= 3 {
+///         FIRST_SEEN.increment(&COUNTERS, 7, iteration);
+///     }
+/// }
+///
+/// assert_eq!(FIRST_SEEN.first_seen(42), Some(0));
+/// assert_eq!(FIRST_SEEN.first_seen(7), Some(3));
+/// assert_eq!(FIRST_SEEN.first_seen(8), None);
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+/// ```
+pub struct FirstSeen<const N: usize>([AtomicU64; N]);
+
+impl<const N: usize> FirstSeen<N> {
+    /// Construct a new record of `N` counters, none of which have been seen.
+    ///
+    /// # Compile-time errors
+    ///
+    /// `N` must not be zero.
+    pub const fn new() -> Self {
+        const { assert!(N != 0, "`N` must not be zero") };
+        FirstSeen([const { AtomicU64::new(0) }; N])
+    }
+
+    /// Record that the counter at `index` was hit at `epoch`, unless it has
+    /// already been recorded.
+    ///
+    /// Only the first call for each index has any effect, even when racing with
+    /// other threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= N`.
+    #[inline]
+    pub fn note(&self, index: usize, epoch: u64) {
+        // Zero means "never seen", so store the epoch plus one.
+        let stored = epoch.saturating_add(1);
+        let _ = self.0[index].compare_exchange(0, stored, Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    /// Increment the counter at `index` in `counters`, and record `epoch` if
+    /// this is its first transition from zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= N`.
+    #[inline]
+    pub fn increment(&self, counters: &Counters<N>, index: usize, epoch: u64) {
+        let counter = &counters[index];
+        if counter.get() == 0 {
+            self.note(index, epoch);
+        }
+        counter.increment();
+    }
+
+    /// Get the epoch at which the counter at `index` was first hit, or `None`
+    /// if it has not been recorded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= N`.
+    #[inline]
+    pub fn first_seen(&self, index: usize) -> Option<u64> {
+        match self.0[index].load(Ordering::Relaxed) {
+            0 => None,
+            stored => Some(stored - 1),
+        }
+    }
+
+    /// Forget every recorded epoch.
+    pub fn reset(&self) {
+        for epoch in &self.0 {
+            epoch.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<const N: usize> Default for FirstSeen<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,142 @@
+use crate::atomic::{AtomicBool, Ordering};
+#[cfg_attr(sancov_disabled, allow(unused_imports))]
+use crate::sys;
+use core::cell::UnsafeCell;
+use core::ops::Index;
+
+/// A collection of `N` boolean coverage flags.
+///
+/// This is the manual equivalent of the flags that clang emits for
+/// `-fsanitize-coverage=inline-bool-flag`. Flags are cheaper than 8-bit
+/// counters when you only care about whether something happened, not how many
+/// times it happened.
+///
+/// Flags must be registered by calling the
+/// [`register`][crate::BoolFlags::register] method.
+///
+/// You can index into `BoolFlags` with `usize` indices to get individual
+/// `Flag`s.
+///
+/// `BoolFlags<N>` has the same representation as `[bool; N]`. You can rely on
+/// this fact and set flags from, for example, JIT code.
+///
+/// # Example
+///
+/// ```
+/// use sancov::BoolFlags;
+///
+/// // Define some flags.
+/// static FLAGS: BoolFlags<4096> = BoolFlags::new();
+///
+/// // Register the flags with the `SanitizerCoverage` consumer.
+/// FLAGS.register();
+///
+/// // Set a flag.
+/// FLAGS[42].set();
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_bool_flag_init(_: *const bool, _: *const bool) {}
+/// ```
+#[repr(transparent)]
+pub struct BoolFlags<const N: usize>(UnsafeCell<[bool; N]>);
+
+unsafe impl<const N: usize> Send for BoolFlags<N> {}
+unsafe impl<const N: usize> Sync for BoolFlags<N> {}
+
+impl<const N: usize> BoolFlags<N> {
+    /// Construct a new set of `N` flags, all unset.
+    ///
+    /// # Compile-time errors
+    ///
+    /// `N` must not be zero.
+    ///
+    /// ```compile_fail
+    /// use sancov::BoolFlags;
+    ///
+    /// // This will not compile!
+    /// let _ = BoolFlags::<0>::new();
+    /// ```
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        const { assert!(N != 0, "`N` must not be zero") };
+        BoolFlags(UnsafeCell::new([false; N]))
+    }
+
+    /// Get the underlying array of flags.
+    #[inline]
+    pub fn as_array(&self) -> &[Flag; N] {
+        unsafe {
+            let ptr: *mut [bool; N] = self.0.get();
+            let ptr: *const [Flag; N] = ptr as _;
+            &*ptr
+        }
+    }
+
+    /// Get the underlying flags as a slice of `bool`s.
+    ///
+    /// # Safety
+    ///
+    /// The flags must not be set or otherwise mutated, from this thread or any
+    /// other, for as long as the returned slice is alive.
+    #[inline]
+    pub unsafe fn as_slice(&self) -> &[bool] {
+        &*self.0.get()
+    }
+
+    /// Register the given flags with the `SanitizerCoverage` consumer.
+    ///
+    /// The `SanitizerCoverage` API does not provide any method of unregistering
+    /// flags, so `&self` must be `'static`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::BoolFlags;
+    ///
+    /// static FLAGS: BoolFlags<4096> = BoolFlags::new();
+    /// FLAGS.register();
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_bool_flag_init(_: *const bool, _: *const bool) {}
+    /// ```
+    pub fn register(&'static self) {
+        enabled! {
+            unsafe {
+                let start = self.0.get() as *const bool;
+                let end = start.add(N);
+                sys::__sanitizer_cov_bool_flag_init(start, end);
+            }
+        }
+    }
+}
+
+impl<const N: usize> Index<usize> for BoolFlags<N> {
+    type Output = Flag;
+
+    #[inline]
+    fn index(&self, index: usize) -> &Self::Output {
+        assert!(index < N);
+        &self.as_array()[index]
+    }
+}
+
+/// A single boolean coverage flag.
+///
+/// It has the same representation as a `bool`. You can rely on this fact and
+/// set this flag from, for example, JIT code.
+#[repr(transparent)]
+pub struct Flag(AtomicBool);
+
+impl Flag {
+    /// Set this flag.
+    #[inline]
+    pub fn set(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Is this flag set?
+    #[inline]
+    pub fn is_set(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
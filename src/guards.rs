@@ -0,0 +1,143 @@
+#[cfg_attr(sancov_disabled, allow(unused_imports))]
+use crate::sys;
+use core::cell::UnsafeCell;
+use core::ops::Index;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// A collection of `N` trace-pc-guard guards.
+///
+/// This is the manual equivalent of the guards that clang emits for
+/// `-fsanitize-coverage=trace-pc-guard`. Guards must be registered by calling
+/// the [`register`][crate::Guards::register] method, at which point the
+/// `SanitizerCoverage` consumer typically assigns each guard a unique, non-zero
+/// ID.
+///
+/// You can index into `Guards` with `usize` indices to get individual `Guard`s.
+///
+/// `Guards<N>` has the same representation as `[u32; N]`. You can rely on this
+/// fact and read or write guards from, for example, JIT code.
+///
+/// # Example
+///
+/// ```
+/// use sancov::Guards;
+///
+/// // Define some guards.
+/// static GUARDS: Guards<4096> = Guards::new();
+///
+/// // Register the guards with the `SanitizerCoverage` consumer.
+/// GUARDS.register();
+///
+/// // Read the ID the consumer assigned to a guard.
+/// let _id = GUARDS[42].get();
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_trace_pc_guard_init(_: *mut u32, _: *mut u32) {}
+/// ```
+#[repr(transparent)]
+pub struct Guards<const N: usize>(UnsafeCell<[u32; N]>);
+
+unsafe impl<const N: usize> Send for Guards<N> {}
+unsafe impl<const N: usize> Sync for Guards<N> {}
+
+impl<const N: usize> Guards<N> {
+    /// Construct a new set of `N` guards.
+    ///
+    /// # Compile-time errors
+    ///
+    /// `N` must not be zero.
+    ///
+    /// ```compile_fail
+    /// use sancov::Guards;
+    ///
+    /// // This will not compile!
+    /// let _ = Guards::<0>::new();
+    /// ```
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        const { assert!(N != 0, "`N` must not be zero") };
+        Guards(UnsafeCell::new([0; N]))
+    }
+
+    /// Get the underlying array of guards.
+    #[inline]
+    pub fn as_array(&self) -> &[Guard; N] {
+        unsafe {
+            let ptr: *mut [u32; N] = self.0.get();
+            let ptr: *const [Guard; N] = ptr as _;
+            &*ptr
+        }
+    }
+
+    /// Register the given guards with the `SanitizerCoverage` consumer.
+    ///
+    /// The `SanitizerCoverage` API does not provide any method of unregistering
+    /// guards, so `&self` must be `'static`.
+    ///
+    /// Consumers typically skip initialization of guards that are already
+    /// non-zero, so repeated registration is usually idempotent.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Guards;
+    ///
+    /// static GUARDS: Guards<4096> = Guards::new();
+    /// GUARDS.register();
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_trace_pc_guard_init(_: *mut u32, _: *mut u32) {}
+    /// ```
+    pub fn register(&'static self) {
+        enabled! {
+            unsafe {
+                let start = self.0.get() as *mut u32;
+                let stop = start.add(N);
+                sys::__sanitizer_cov_trace_pc_guard_init(start, stop);
+            }
+        }
+    }
+}
+
+impl<const N: usize> Index<usize> for Guards<N> {
+    type Output = Guard;
+
+    #[inline]
+    fn index(&self, index: usize) -> &Self::Output {
+        assert!(index < N);
+        &self.as_array()[index]
+    }
+}
+
+/// A single trace-pc-guard guard.
+///
+/// It has the same representation as a `u32`. You can rely on this fact and
+/// read or write this guard from, for example, JIT code.
+#[repr(transparent)]
+pub struct Guard(AtomicU32);
+
+impl Guard {
+    /// Get this guard's current value.
+    ///
+    /// After registration, this is usually the ID that the `SanitizerCoverage`
+    /// consumer assigned to this guard.
+    #[inline]
+    pub fn get(&self) -> u32 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Set this guard's value.
+    #[inline]
+    pub fn set(&self, value: u32) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+
+    /// Get a raw pointer to this guard's slot.
+    ///
+    /// This is the pointer that clang-instrumented code passes to
+    /// `__sanitizer_cov_trace_pc_guard`, and that JIT code can store into.
+    #[inline]
+    pub fn as_ptr(&self) -> *mut u32 {
+        self.0.as_ptr()
+    }
+}
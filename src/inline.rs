@@ -0,0 +1,95 @@
+//! Discovery of counters placed in the `__sancov_cntrs` section.
+
+#[cfg(feature = "std")]
+use crate::registry;
+use sancov_sys as sys;
+
+/// Register every counter in the `__sancov_cntrs` section with the
+/// `SanitizerCoverage` consumer, as a single region.
+///
+/// This is the section that `-fsanitize-coverage=inline-8bit-counters` places
+/// its counters in. A [`Counters<N>`][crate::Counters] static can be placed
+/// there with `#[link_section]`, as below, or with the `inline_counters!` macro
+/// when the `macros` feature is enabled. The section is found via
+/// the `__start___sancov_cntrs` and `__stop___sancov_cntrs` symbols that the
+/// linker synthesizes for it, so no per-static registration is needed.
+///
+/// Returns `false`, without calling the consumer, if the section does not
+/// exist or is empty, if no consumer is linked in, or if weak references are
+/// not supported on this target (currently only ELF targets are supported).
+///
+/// **Do not call this in a binary that is itself compiled with
+/// `inline-8bit-counters` instrumentation.** The compiler already emits a
+/// module constructor that registers the whole section, including any counters
+/// placed there by this crate, and registering it twice would make the
+/// consumer count every edge twice.
+///
+/// # Linker requirements
+///
+/// GNU ld, gold, and lld only synthesize `__start_`/`__stop_` symbols for
+/// output sections whose names are valid C identifiers, which
+/// `__sancov_cntrs` is. With a custom linker script, the section must be kept
+/// and the symbols defined explicitly, for example:
+///
+/// ```text
+/// __sancov_cntrs : {
+///     PROVIDE_HIDDEN(__start___sancov_cntrs = .);
+///     KEEP(*(__sancov_cntrs))
+///     PROVIDE_HIDDEN(__stop___sancov_cntrs = .);
+/// }
+/// ```
+///
+/// Under `--gc-sections`, sections referenced through their `__start_`
+/// symbols are retained by default, unless `-z start-stop-gc` is passed.
+///
+/// The section bounds are per linked object, so each shared library has its
+/// own section and must call this function from its own code.
+///
+/// With the `register_once` feature, the `SanitizerCoverage` consumer is only
+/// called once for the section, however many times this function is called.
+///
+/// # Example
+///
+/// ```
+/// use sancov::Counters;
+///
+/// #[used]
+/// #[link_section = "__sancov_cntrs"]
+/// static PARSER: Counters<1024> = Counters::new();
+///
+/// #[used]
+/// #[link_section = "__sancov_cntrs"]
+/// static EVAL: Counters<4096> = Counters::new();
+///
+/// # #[cfg(target_os = "linux")]
+/// assert!(sancov::register_inline());
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(start: *const u8, end: *const u8) {
+/// #     assert_eq!(end as usize - start as usize, 1024 + 4096);
+/// # }
+/// ```
+pub fn register_inline() -> bool {
+    if cfg!(sancov_disabled) {
+        return false;
+    }
+    let (start, stop) = match sys::sancov_cntrs_section() {
+        Some((start, stop)) if start < stop => (start, stop),
+        _ => return false,
+    };
+    let init = match sys::weak_8bit_counters_init() {
+        Some(init) => init,
+        None => return false,
+    };
+    #[cfg(feature = "register_once")]
+    unsafe {
+        registry::record_once(start, stop as usize - start as usize, true, init);
+    }
+    #[cfg(not(feature = "register_once"))]
+    unsafe {
+        init(start, stop);
+        #[cfg(feature = "std")]
+        registry::record(start, stop as usize - start as usize, true);
+    }
+    true
+}
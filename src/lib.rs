@@ -1,12 +1,117 @@
 #![doc = include_str!("../README.md")]
 #![no_std]
 #![deny(missing_docs)]
+#![cfg_attr(sancov_nightly, feature(portable_simd))]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+use crate::atomic::{AtomicU8, Ordering};
 use core::cell::UnsafeCell;
-use core::ops::Index;
-use core::sync::atomic::{AtomicU8, Ordering};
+use core::ops::{Index, Range};
 use sancov_sys as sys;
 
+/// Expands to its body, unless the crate is built with `--cfg sancov_disabled`,
+/// in which case it expands to nothing.
+macro_rules! enabled {
+    ($($body:tt)*) => {
+        #[cfg(not(sancov_disabled))]
+        {
+            $($body)*
+        }
+    };
+}
+
+mod accumulator;
+mod atomic;
+mod cell;
+#[cfg(all(feature = "contention_stats", target_has_atomic = "64"))]
+mod contention;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(target_has_atomic = "64")]
+mod first_seen;
+mod flags;
+#[cfg(target_has_atomic = "32")]
+mod guards;
+mod inline;
+#[cfg(feature = "libfuzzer")]
+mod libfuzzer;
+#[cfg(feature = "std")]
+mod local;
+#[cfg(feature = "macros")]
+mod macros;
+mod names;
+mod once;
+#[cfg(feature = "hash_increment")]
+mod overlay;
+#[cfg(feature = "std")]
+mod panic_edge;
+#[cfg(target_has_atomic = "ptr")]
+mod pcs;
+#[cfg(all(feature = "profiling", target_has_atomic = "64"))]
+mod profiling;
+pub mod raw;
+mod reader;
+#[cfg(feature = "std")]
+mod registry;
+mod scope;
+mod serialize;
+mod snapshot;
+mod sparse;
+#[cfg(target_has_atomic = "ptr")]
+mod stack;
+mod stats;
+mod trace;
+mod variant;
+#[cfg(feature = "alloc")]
+mod vec;
+mod wide;
+
+pub use accumulator::Accumulator;
+pub use cell::{CounterCell, CountersCell};
+#[cfg(target_has_atomic = "64")]
+pub use first_seen::FirstSeen;
+pub use flags::{BoolFlags, Flag};
+#[cfg(target_has_atomic = "32")]
+pub use guards::{Guard, Guards};
+pub use inline::register_inline;
+#[cfg(feature = "std")]
+pub use local::ThreadLocalCounters;
+pub use names::Names;
+pub use once::{OnceEdge, OnceEdges};
+#[cfg(feature = "hash_increment")]
+pub use overlay::SparseOverlay;
+#[cfg(target_has_atomic = "ptr")]
+pub use pcs::{PcTable, PC_FLAG_FUNCTION_ENTRY};
+#[cfg(all(feature = "profiling", target_has_atomic = "64"))]
+pub use profiling::Profiling;
+pub use reader::CountersReader;
+#[cfg(feature = "std")]
+pub use registry::{registered_regions, reset_all};
+pub use scope::ScopeGuard;
+pub use serialize::LenMismatch;
+pub use snapshot::{Snapshot, SnapshotError, SNAPSHOT_MAGIC, SNAPSHOT_VERSION};
+pub use sparse::SparseError;
+#[cfg(target_has_atomic = "ptr")]
+pub use stack::StackDepth;
+#[cfg(target_has_atomic = "32")]
+pub use trace::trace_pc_guard;
+pub use trace::{
+    trace_cmp, trace_cmp1, trace_cmp2, trace_cmp4, trace_cmp8, trace_cmp_pc, trace_const_cmp,
+    trace_const_cmp1, trace_const_cmp2, trace_const_cmp4, trace_const_cmp8, trace_div4, trace_div8,
+    trace_gep, trace_memcmp, trace_pc_indir, trace_strcmp, trace_switch, SwitchCases, TraceCmp,
+};
+pub use variant::{CoverageIndex, VariantCounters};
+#[cfg(feature = "alloc")]
+pub use vec::CountersVec;
+#[cfg(target_has_atomic = "16")]
+pub use wide::{Counter16, Counters16};
+#[cfg(target_has_atomic = "32")]
+pub use wide::{Counter32, Counters32};
+
 /// An collection of `N` counters.
 ///
 /// Counters must be registered by calling the
@@ -62,19 +167,175 @@ impl<const N: usize> Counters<N> {
     /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
     /// ```
     ///
-    /// # Panics
+    /// # Compile-time errors
     ///
-    /// Panics if `N` is zero.
+    /// `N` must not be zero; this is checked when the constructor is
+    /// instantiated, so it is a build error rather than a panic.
     ///
-    /// ```should_panic
+    /// ```compile_fail
     /// use sancov::Counters;
     ///
-    /// // This will panic!
+    /// // This will not compile!
     /// let _ = Counters::<0>::new();
     /// ```
     pub const fn new() -> Self {
-        let _n_cannot_be_zero = [()][(N == 0) as usize];
-        Counters(UnsafeCell::new([0; N]))
+        Self::from_array([0; N])
+    }
+
+    /// Construct a new set of `N` counters with the given initial values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// let saved = [0, 1, 2, 3];
+    /// let counters = Counters::from_array(saved);
+    /// assert_eq!(counters.into_array(), saved);
+    /// ```
+    ///
+    /// # Compile-time errors
+    ///
+    /// `N` must not be zero.
+    pub const fn from_array(values: [u8; N]) -> Self {
+        const { assert!(N != 0, "`N` must not be zero") };
+        Counters(UnsafeCell::new(values))
+    }
+
+    /// Construct a new set of `N` counters from `(index, value)` pairs.
+    ///
+    /// Every counter starts at zero, and then each pair sets the counter at
+    /// `index` to `value`, so if an index appears more than once, the last
+    /// pair wins. This is convenient for tests, and for resuming from a sparse
+    /// checkpoint that only lists the non-zero counters.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// let counters = Counters::<8>::seeded([(1, 3), (6, 255)]);
+    /// assert_eq!(counters.into_array(), [0, 3, 0, 0, 0, 0, 255, 0]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if any `index >= N`, in the same way as indexing does, so that
+    /// a corrupt checkpoint is not silently truncated.
+    ///
+    /// # Compile-time errors
+    ///
+    /// `N` must not be zero.
+    pub fn seeded<I>(pairs: I) -> Self
+    where
+        I: IntoIterator<Item = (usize, u8)>,
+    {
+        let mut values = [0; N];
+        for (index, value) in pairs {
+            values[index] = value;
+        }
+        Self::from_array(values)
+    }
+
+    /// Consume the counters, returning their values.
+    #[inline]
+    pub fn into_array(self) -> [u8; N] {
+        self.0.into_inner()
+    }
+
+    /// Treat an externally-owned region of `N` bytes as counters, without
+    /// copying.
+    ///
+    /// This relies on `Counters<N>` having the same representation as
+    /// `[u8; N]`, and is useful when the coverage map lives in memory that
+    /// this crate did not allocate, such as a shared memory region that a
+    /// fork-server parent reads out-of-band.
+    ///
+    /// # Safety
+    ///
+    /// * `ptr` must be non-null and valid for reads and writes of `N` bytes
+    ///   for the rest of the program, i.e. the region must never be unmapped
+    ///   or freed.
+    ///
+    /// * Within this process, the region must only be accessed through the
+    ///   returned reference (or other references returned by this function)
+    ///   or through atomic operations, never through non-atomic Rust
+    ///   references. Other processes may read or write the region, with the
+    ///   usual caveat that their accesses race with ours.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// // Stands in for, e.g., an `mmap`ed shared memory region.
+    /// let shmem: &'static mut [u8; 4096] = Box::leak(Box::new([0; 4096]));
+    ///
+    /// let counters: &'static Counters<4096> =
+    ///     unsafe { Counters::from_mut_ptr(shmem.as_mut_ptr()) };
+    /// counters.register();
+    /// counters[42].increment();
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    #[inline]
+    pub unsafe fn from_mut_ptr(ptr: *mut u8) -> &'static Counters<N> {
+        &*(ptr as *const Counters<N>)
+    }
+
+    /// Treat an externally-owned region of `len` bytes as counters, checking
+    /// that `len` is `N`.
+    ///
+    /// This is [`from_mut_ptr`][crate::Counters::from_mut_ptr] for foreign
+    /// hosts that pass a coverage buffer as a `(ptr, len)` pair: rather than
+    /// trusting that the buffer is the size this code was compiled for, it
+    /// returns a [`LenMismatch`][crate::LenMismatch] error if it is not.
+    ///
+    /// # Safety
+    ///
+    /// If `len == N`, the same requirements as for `from_mut_ptr` apply:
+    ///
+    /// * `ptr` must be non-null and valid for reads and writes of `len` bytes
+    ///   for the rest of the program, since the returned reference is
+    ///   `'static`. A host that frees or unmaps the buffer must not do so
+    ///   while this process may still use the counters.
+    ///
+    /// * Within this process, the region must only be accessed through
+    ///   references returned by this function or `from_mut_ptr`, or through
+    ///   atomic operations, never through non-atomic Rust references.
+    ///
+    /// If `len != N`, `ptr` is not used.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::{Counters, LenMismatch};
+    ///
+    /// // Stands in for a buffer owned by a C host.
+    /// let buf: &'static mut [u8] = Box::leak(vec![0; 4096].into_boxed_slice());
+    /// let (ptr, len) = (buf.as_mut_ptr(), buf.len());
+    ///
+    /// assert_eq!(
+    ///     unsafe { Counters::<1024>::from_raw_parts(ptr, len) }.err(),
+    ///     Some(LenMismatch { expected: 1024, found: 4096 }),
+    /// );
+    ///
+    /// let counters = unsafe { Counters::<4096>::from_raw_parts(ptr, len) }.unwrap();
+    /// counters[42].increment();
+    /// ```
+    #[inline]
+    pub unsafe fn from_raw_parts(
+        ptr: *mut u8,
+        len: usize,
+    ) -> Result<&'static Counters<N>, LenMismatch> {
+        if len != N {
+            return Err(LenMismatch {
+                expected: N,
+                found: len,
+            });
+        }
+        Ok(Self::from_mut_ptr(ptr))
     }
 
     /// Get the underying array of counters.
@@ -88,6 +349,166 @@ impl<const N: usize> Counters<N> {
         }
     }
 
+    /// Get the underlying counters as a slice of raw bytes.
+    ///
+    /// The returned slice has length `N` and points at the same memory that
+    /// the `SanitizerCoverage` consumer observes. This is useful for copying
+    /// the whole map somewhere else, e.g. into a shared memory region, in one
+    /// go.
+    ///
+    /// # Safety
+    ///
+    /// The counters must not be incremented, reset, or otherwise mutated,
+    /// from this thread or any other, for as long as the returned slice is
+    /// alive.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<16> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// COUNTERS[3].increment();
+    ///
+    /// let mut shmem = [0; 16];
+    /// shmem.copy_from_slice(unsafe { COUNTERS.as_slice() });
+    /// assert_eq!(shmem[3], 1);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    #[inline]
+    pub unsafe fn as_slice(&self) -> &[u8] {
+        &*self.0.get()
+    }
+
+    /// Get the underlying counters as a mutable slice of raw bytes.
+    ///
+    /// The returned slice has length `N` and points at the same memory that
+    /// the `SanitizerCoverage` consumer observes.
+    ///
+    /// # Safety
+    ///
+    /// The counters must not be accessed in any other way, from this thread or
+    /// any other, for as long as the returned slice is alive. This includes
+    /// through other slices returned by this method or by
+    /// [`as_slice`][crate::Counters::as_slice], and through `Counter`s. Note
+    /// that the `SanitizerCoverage` consumer may read the counters at any
+    /// time, so writes through this slice may be observed by it partially.
+    #[inline]
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn as_bytes_mut(&self) -> &mut [u8] {
+        &mut *self.0.get()
+    }
+
+    /// Get the number of counters, i.e. `N`.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    /// Always returns `false`, since `N` is never zero.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Get the number of counters, i.e. `N`, without an instance.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// assert_eq!(Counters::<4096>::capacity(), 4096);
+    /// ```
+    #[inline]
+    pub const fn capacity() -> usize {
+        N
+    }
+
+    /// Get the counter at `index`, or `None` if `index >= N`.
+    ///
+    /// This is the non-panicking alternative to indexing.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<16> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// if let Some(counter) = COUNTERS.get(15) {
+    ///     counter.increment();
+    /// }
+    /// assert!(COUNTERS.get(16).is_none());
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&Counter> {
+        self.as_array().get(index)
+    }
+
+    /// Iterate over the counters, in ascending index order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<16> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// COUNTERS[1].increment();
+    /// COUNTERS[2].increment();
+    /// COUNTERS[2].increment();
+    ///
+    /// // Sum all of the counters' current values.
+    /// let sum: usize = COUNTERS.iter().map(|c| c.get() as usize).sum();
+    /// assert_eq!(sum, 3);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    #[inline]
+    pub fn iter(&self) -> core::slice::Iter<'_, Counter> {
+        self.as_array().iter()
+    }
+
+    /// Iterate over the counters' indices and current values.
+    ///
+    /// Yields `(index, value)` pairs, reading each value with a relaxed load
+    /// as it is reached. The indices are guaranteed to be yielded exactly once
+    /// each, in ascending order, from `0` to `N - 1`, so tools that write
+    /// coverage dumps or diff maps can rely on the order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<4> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// COUNTERS[2].increment();
+    /// assert!(COUNTERS.iter_indexed().eq([(0, 0), (1, 0), (2, 1), (3, 0)]));
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    #[inline]
+    pub fn iter_indexed(&self) -> impl Iterator<Item = (usize, u8)> + '_ {
+        self.atomics()
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (i, c.load(Ordering::Relaxed)))
+    }
+
     /// Register the given counters with the `SanitizerCoverage` consumer.
     ///
     /// The `SanitizerCoverage` API unfortunately does not provide any method of
@@ -97,6 +518,44 @@ impl<const N: usize> Counters<N> {
     /// performant. Consider using `std::sync::Once` or [the `ctor`
     /// crate](https://crates.io/crates/ctor).
     ///
+    /// With the `std` feature, the counters are also recorded in a
+    /// process-wide registry; see `sancov::registered_regions` and
+    /// `sancov::reset_all`.
+    ///
+    /// With the `register_once` feature, which implies `std`, registration is
+    /// guarded by that registry: the `SanitizerCoverage` consumer is only ever
+    /// called once for a given set of counters, even when `register` is called
+    /// repeatedly, or concurrently from several threads, and every call returns
+    /// only after the counters have been registered. This is useful when
+    /// independently-initialized modules, such as dynamically-loaded plugins,
+    /// may each register the same static.
+    ///
+    /// ```
+    /// # #[cfg(feature = "register_once")] {
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<4096> = Counters::new();
+    ///
+    /// let threads: Vec<_> = (0..8)
+    ///     .map(|_| std::thread::spawn(|| COUNTERS.register()))
+    ///     .collect();
+    /// for t in threads {
+    ///     t.join().unwrap();
+    /// }
+    /// COUNTERS.register();
+    ///
+    /// assert_eq!(INIT_CALLS.load(Ordering::Relaxed), 1);
+    /// # }
+    /// #
+    /// # use std::sync::atomic::{AtomicUsize, Ordering};
+    /// # static INIT_CALLS: AtomicUsize = AtomicUsize::new(0);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {
+    /// #     INIT_CALLS.fetch_add(1, Ordering::Relaxed);
+    /// # }
+    /// ```
+    ///
     /// # Example
     ///
     /// ```
@@ -112,86 +571,1757 @@ impl<const N: usize> Counters<N> {
     /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
     /// ```
     pub fn register(&'static self) {
-        unsafe {
+        enabled! {
             let start = self.as_array().as_ptr() as *const u8;
-            let end = start.add(N) as *const u8;
-            sys::__sanitizer_cov_8bit_counters_init(start, end);
+            #[cfg(feature = "register_once")]
+            unsafe {
+                registry::record_once(start, N, true, sys::__sanitizer_cov_8bit_counters_init);
+            }
+            #[cfg(not(feature = "register_once"))]
+            unsafe {
+                sys::__sanitizer_cov_8bit_counters_init(start, start.add(N));
+                #[cfg(feature = "std")]
+                registry::record(start, N, true);
+            }
         }
     }
 
-    /// Increment the counter at index `fxhash(x) % self.len()`.
+    /// Register the given counters with the `SanitizerCoverage` consumer, and
+    /// return the start and length of the region that was registered.
     ///
-    /// This allows you to map an unbounded number of logical counters down onto
-    /// a bounded number of actual counters.
+    /// This is [`register`][crate::Counters::register], for harnesses that hand
+    /// the registered region to another subsystem, such as a shared memory
+    /// mirror, and want exactly what the consumer received rather than
+    /// recomputing it. The region is always all `N` counters.
     ///
-    /// Useful when:
+    /// The pointer is derived from `&'static self`, so it is valid for the
+    /// rest of the program, and may be used to read the counters. Like the
+    /// consumer's own copy, it aliases live counters that may be incremented
+    /// at any time, so it must only be used for atomic or volatile accesses,
+    /// or for racy reads that tolerate torn values; see
+    /// [`from_mut_ptr`][crate::Counters::from_mut_ptr] for the rules on
+    /// accessing the region. When instrumentation is disabled with
+    /// `--cfg sancov_disabled`, nothing is registered, but the region is still
+    /// returned.
     ///
-    /// 1. You have a dynamic number of logical counters, but you have to choose
-    ///    a static number of actual counters at initialization time when
-    ///    registering the counters because of `SanitizerCoverage` API
-    ///    constraints. Note that using this method is preferable to doing
-    ///    `counters[i % counters.len()].increment()` in this situation, since
-    ///    that can suffer from harmonics.
+    /// # Example
     ///
-    /// 2. You have very many and very sparsely incremented logical counters. So
-    ///    many logical counters that the performance of the consumer iterating
-    ///    over them all would be severely impacted if you had that many actual
-    ///    counters.
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<4096> = Counters::new();
+    ///
+    /// let (start, len) = COUNTERS.register_range_ptr();
+    /// assert_eq!(len, 4096);
+    ///
+    /// COUNTERS[7].increment();
+    /// assert_eq!(unsafe { start.add(7).read_volatile() }, 1);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    pub fn register_range_ptr(&'static self) -> (*const u8, usize) {
+        self.register();
+        (self.as_array().as_ptr() as *const u8, N)
+    }
+
+    /// Register only the counters in `range` with the `SanitizerCoverage`
+    /// consumer.
+    ///
+    /// This supports staged registration: allocating one large `Counters<N>`
+    /// up front, and exposing more of it to the consumer as parts of the
+    /// program, such as plugins, come online.
+    ///
+    /// The consumer treats each registration as a separate region, and does
+    /// not merge overlapping ones. libFuzzer, for example, only ignores a
+    /// registration that starts where the immediately preceding one did, so
+    /// counters that are covered by more than one registered range are
+    /// counted more than once. To grow the registered part of the counters,
+    /// register the new, disjoint range, rather than a larger range that
+    /// includes the old one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is empty or out of bounds.
     ///
     /// # Example
     ///
     /// ```
     /// use sancov::Counters;
     ///
-    /// static COUNTERS: Counters<16> = Counters::new();
-    /// COUNTERS.register();
+    /// static COUNTERS: Counters<65536> = Counters::new();
     ///
-    /// // Increment the "i^th" counter, where `i` is larger than how many
-    /// // actual counters we have.
-    /// COUNTERS.hash_increment(&69);
-    /// COUNTERS.hash_increment(&420);
+    /// // Expose the counters for the core at startup...
+    /// COUNTERS.register_range(0..1024);
     ///
-    /// // Or increment counters named by anything that is hashable!
-    /// COUNTERS.hash_increment("wheelies");
-    /// COUNTERS.hash_increment("won't");
-    /// COUNTERS.hash_increment("pop");
-    /// COUNTERS.hash_increment("themselves!");
+    /// // ...and then the next block once a plugin is loaded.
+    /// COUNTERS.register_range(1024..4096);
     /// #
     /// # #[no_mangle]
     /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
     /// ```
-    #[inline]
-    #[cfg(feature = "hash_increment")]
-    pub fn hash_increment<T>(&self, x: &T)
-    where
-        T: ?Sized + core::hash::Hash,
-    {
-        assert_ne!(N, 0);
-        let i = fxhash::hash(x) % N;
-        self[i].increment();
+    pub fn register_range(&'static self, range: Range<usize>) {
+        let region = &self.atomics()[range];
+        assert!(!region.is_empty(), "cannot register an empty range");
+        enabled! {
+            let start = region.as_ptr() as *const u8;
+            let len = region.len();
+            #[cfg(feature = "register_once")]
+            unsafe {
+                registry::record_once(start, len, true, sys::__sanitizer_cov_8bit_counters_init);
+            }
+            #[cfg(not(feature = "register_once"))]
+            unsafe {
+                sys::__sanitizer_cov_8bit_counters_init(start, start.add(len));
+                #[cfg(feature = "std")]
+                registry::record(start, len, true);
+            }
+        }
     }
-}
 
-impl<const N: usize> Index<usize> for Counters<N> {
-    type Output = Counter;
-
-    #[inline]
-    fn index(&self, index: usize) -> &Self::Output {
-        assert!(index < N);
-        &self.as_array()[index]
+    /// Register the given counters together with their parallel PC table.
+    ///
+    /// This registers the counters and then immediately registers `pcs`, which
+    /// is the order consumers expect. Because both have the same `N`, the two
+    /// regions are statically guaranteed to be the same length.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::{Counters, PcTable};
+    ///
+    /// static COUNTERS: Counters<4096> = Counters::new();
+    /// static PCS: PcTable<4096> = PcTable::new();
+    ///
+    /// PCS.set(42, 0x1234, true);
+    /// COUNTERS.register_with(&PCS);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_pcs_init(_: *const usize, _: *const usize) {}
+    /// ```
+    #[cfg(target_has_atomic = "ptr")]
+    pub fn register_with(&'static self, pcs: &'static PcTable<N>) {
+        self.register();
+        pcs.register();
     }
-}
 
-/// A single 8-bit counter.
-///
-/// It can be incremented.
-///
+    /// Register the given counters with the `SanitizerCoverage` consumer, or
+    /// return an error if no consumer is linked in.
+    ///
+    /// Unlike [`register`][crate::Counters::register], this does not require
+    /// `__sanitizer_cov_8bit_counters_init` to be defined at link time. If it
+    /// isn't, the counters would never be observed by anything, and this
+    /// returns [`NoConsumer`][crate::NoConsumer].
+    ///
+    /// Note that this cannot detect the no-op default definitions that some
+    /// sanitizer runtimes provide; it only detects the complete absence of a
+    /// consumer. On targets where weak references are not supported, this
+    /// always registers the counters and returns `Ok`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<4096> = Counters::new();
+    ///
+    /// if COUNTERS.try_register().is_err() {
+    ///     eprintln!("warning: no `SanitizerCoverage` consumer is linked in");
+    /// }
+    /// ```
+    pub fn try_register(&'static self) -> Result<(), NoConsumer> {
+        if cfg!(sancov_disabled) {
+            return Ok(());
+        }
+        let init = sys::weak_8bit_counters_init().ok_or(NoConsumer)?;
+        let start = self.as_array().as_ptr() as *const u8;
+        #[cfg(feature = "register_once")]
+        unsafe {
+            registry::record_once(start, N, true, init);
+        }
+        #[cfg(not(feature = "register_once"))]
+        unsafe {
+            init(start, start.add(N));
+            #[cfg(feature = "std")]
+            registry::record(start, N, true);
+        }
+        Ok(())
+    }
+
+    /// Issue a sequentially-consistent memory fence.
+    ///
+    /// All counter operations in this crate use `Ordering::Relaxed` by default.
+    /// That is normally correct for fuzzing feedback: each counter is
+    /// independent, the consumer only needs to eventually observe increments,
+    /// and it typically reads the counters on the same thread that incremented
+    /// them, after the fuzz target returns, which is ordered by program order
+    /// alone.
+    ///
+    /// When a different thread reads the counters, for example a monitoring
+    /// thread taking periodic checkpoints, relaxed increments may become
+    /// visible to it in any order. To get a clean readout, call `barrier` after
+    /// the increments you want to be visible and before signalling the reader
+    /// through some other atomic (e.g. a store to an `AtomicBool`), and have
+    /// the reader acquire that signal (or call `barrier` itself) before
+    /// reading. Everything incremented before the writer's barrier is then
+    /// visible to the reader.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    ///
+    /// static COUNTERS: Counters<16> = Counters::new();
+    /// static CHECKPOINT: AtomicBool = AtomicBool::new(false);
+    ///
+    /// // Writer.
+    /// COUNTERS[3].increment();
+    /// COUNTERS.barrier();
+    /// CHECKPOINT.store(true, Ordering::Relaxed);
+    ///
+    /// // Reader, possibly on another thread.
+    /// if CHECKPOINT.load(Ordering::Relaxed) {
+    ///     COUNTERS.barrier();
+    ///     assert_eq!(COUNTERS.count_nonzero(), 1);
+    /// }
+    /// ```
+    #[inline]
+    pub fn barrier(&self) {
+        core::sync::atomic::fence(Ordering::SeqCst);
+    }
+
+    /// Reset every counter back to zero.
+    ///
+    /// This does not re-register the counters; they remain registered with the
+    /// `SanitizerCoverage` consumer.
+    ///
+    /// Resetting while the consumer is concurrently reading the counters is
+    /// racy, in the sense that the consumer may observe some counters that
+    /// have been reset and some that have not, but it is not unsafe.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<4096> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// COUNTERS[42].increment();
+    ///
+    /// // Clear coverage between test cases.
+    /// COUNTERS.reset();
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    pub fn reset(&self) {
+        zero(self.atomics());
+    }
+
+    /// Reset the counters in `range` back to zero, leaving the rest untouched.
+    ///
+    /// This is useful when part of the counters is per-iteration scratch space
+    /// that is cleared frequently, while the rest accumulates across the whole
+    /// run. Like [`reset`][crate::Counters::reset], it is racy, but not unsafe,
+    /// with respect to a concurrent consumer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds, i.e. if `range.start > range.end` or
+    /// `range.end > N`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// // Counters `0..64` accumulate, and `64..128` are per-iteration.
+    /// static COUNTERS: Counters<128> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// COUNTERS[1].increment();
+    /// COUNTERS[65].increment();
+    ///
+    /// COUNTERS.reset_range(64..128);
+    /// assert_eq!(COUNTERS[1].get(), 1);
+    /// assert_eq!(COUNTERS[65].get(), 0);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    pub fn reset_range(&self, range: Range<usize>) {
+        zero(&self.atomics()[range]);
+    }
+
+    /// Set the counter at `index` to one if it is zero, and otherwise leave it
+    /// untouched.
+    ///
+    /// This is for edges where only breadth matters, i.e. whether they were
+    /// hit at all, not how many times. It loses hit-count resolution by design:
+    /// a counter that is only ever set this way is either zero or one. In
+    /// exchange, once a counter is non-zero, this is a single relaxed load with
+    /// no store, so hot edges cause no write traffic, and no cache-line
+    /// contention between threads. The transition from zero is a relaxed
+    /// compare-and-swap.
+    ///
+    /// See also [`BoolFlags`][crate::BoolFlags], which is the dedicated type
+    /// for breadth-only coverage.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= N`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<16> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// for _ in 0..1000 {
+    ///     COUNTERS.set_if_zero(3);
+    /// }
+    /// assert_eq!(COUNTERS[3].get(), 1);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    #[inline]
+    #[cfg_attr(sancov_disabled, allow(unused_variables))]
+    pub fn set_if_zero(&self, index: usize) {
+        let counter = &self.atomics()[index];
+        enabled! {
+            if counter.load(Ordering::Relaxed) == 0 {
+                let _ = counter.compare_exchange(0, 1, Ordering::Relaxed, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Count how many counters are non-zero, i.e. have been incremented since
+    /// they were created or last reset.
+    ///
+    /// This is useful for measuring how full the counters are, e.g. when
+    /// choosing `N` for use with `hash_increment`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<16> = Counters::new();
+    /// COUNTERS.register();
+    /// assert_eq!(COUNTERS.count_nonzero(), 0);
+    ///
+    /// COUNTERS[0].increment();
+    /// COUNTERS[7].increment();
+    /// COUNTERS[7].increment();
+    /// COUNTERS[15].increment();
+    ///
+    /// let count = COUNTERS.count_nonzero();
+    /// assert!(count > 0 && count <= 16);
+    /// assert_eq!(count, 3);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    pub fn count_nonzero(&self) -> usize {
+        let mut chunks = self.atomics().chunks_exact(WORD);
+        let mut count = 0;
+        for chunk in &mut chunks {
+            let word = load_word(chunk);
+            if word != 0 {
+                count += nonzero_bytes(word);
+            }
+        }
+        count
+            + chunks
+                .remainder()
+                .iter()
+                .filter(|b| b.load(Ordering::Relaxed) != 0)
+                .count()
+    }
+
+    /// Merge `other`'s counters into these counters, setting each counter to
+    /// the maximum of its own and `other`'s value.
+    ///
+    /// This is the basic operation for building up a cumulative coverage map
+    /// across many runs. See also
+    /// [`merge_from_saturating`][crate::Counters::merge_from_saturating].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// let total = Counters::<16>::new();
+    /// let run = Counters::<16>::new();
+    ///
+    /// run[3].increment();
+    /// total.merge_from(&run);
+    /// assert_eq!(total.count_nonzero(), 1);
+    /// ```
+    pub fn merge_from(&self, other: &Counters<N>) {
+        for (a, b) in self.atomics().iter().zip(other.atomics()) {
+            let b = b.load(Ordering::Relaxed);
+            if b > a.load(Ordering::Relaxed) {
+                a.store(b, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Merge `other`'s counters into these counters, adding each of `other`'s
+    /// values to the corresponding counter, saturating at `u8::MAX`.
+    ///
+    /// See also [`merge_from`][crate::Counters::merge_from].
+    pub fn merge_from_saturating(&self, other: &Counters<N>) {
+        for (a, b) in self.atomics().iter().zip(other.atomics()) {
+            let b = b.load(Ordering::Relaxed);
+            if b != 0 {
+                let count = a.load(Ordering::Relaxed);
+                a.store(count.saturating_add(b), Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Add a map of per-counter deltas to these counters.
+    ///
+    /// Each non-zero delta is added to its corresponding counter with
+    /// [`Counter::increment_by`][crate::Counter::increment_by], i.e. with the
+    /// same "NeverZero" overflow policy as
+    /// [`increment`][crate::Counter::increment], so a counter that receives a
+    /// delta is never left at zero. This is useful for folding coverage that was
+    /// accumulated out-of-band, in a detached, non-atomic buffer, back into the
+    /// live counters.
+    ///
+    /// See also [`merge_from_saturating`][crate::Counters::merge_from_saturating],
+    /// which saturates instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<16> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// let mut deltas = [0; 16];
+    /// deltas[3] = 2;
+    /// deltas[9] = 1;
+    ///
+    /// COUNTERS[3].increment();
+    /// COUNTERS.add_deltas(&deltas);
+    /// assert_eq!(COUNTERS[3].get(), 3);
+    /// assert_eq!(COUNTERS[9].get(), 1);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    #[cfg_attr(sancov_disabled, allow(unused_variables))]
+    pub fn add_deltas(&self, deltas: &[u8; N]) {
+        enabled! {
+            for (c, &d) in self.iter().zip(deltas) {
+                if d != 0 {
+                    c.increment_by(d);
+                }
+            }
+        }
+    }
+
+    /// Take a snapshot of the counters' current values.
+    ///
+    /// See [`Snapshot`][crate::Snapshot] for an example.
+    pub fn snapshot(&self) -> Snapshot<N> {
+        let mut values = [0; N];
+        for (v, c) in values.iter_mut().zip(self.atomics()) {
+            *v = c.load(Ordering::Relaxed);
+        }
+        Snapshot(values)
+    }
+
+    /// Save the current values of the counters into `into`.
+    ///
+    /// Together with [`restore_checkpoint`][crate::Counters::restore_checkpoint],
+    /// this lets the coverage map be checkpointed and restored alongside the
+    /// rest of the program's state, for example by a snapshot fuzzer that
+    /// restores a VM snapshot between inputs. Unlike
+    /// [`snapshot`][crate::Counters::snapshot], it writes into a caller-owned
+    /// buffer, so that large maps do not need to be copied through the stack.
+    ///
+    /// The copy is done a word at a time, with relaxed loads. It is racy, but
+    /// not unsafe, with respect to concurrent increments.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<4096> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// let mut checkpoint = Box::new([0; 4096]);
+    /// COUNTERS[1].increment();
+    /// COUNTERS.save_checkpoint(&mut checkpoint);
+    ///
+    /// // Run an input from the snapshot...
+    /// COUNTERS[2].increment();
+    ///
+    /// // ...and then roll back to it.
+    /// COUNTERS.restore_checkpoint(&checkpoint);
+    /// assert_eq!(COUNTERS[1].get(), 1);
+    /// assert_eq!(COUNTERS[2].get(), 0);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    pub fn save_checkpoint(&self, into: &mut [u8; N]) {
+        let mut counters = self.atomics().chunks_exact(WORD);
+        let mut into_words = into.chunks_exact_mut(WORD);
+        for (c, v) in (&mut counters).zip(&mut into_words) {
+            v.copy_from_slice(&load_word(c).to_ne_bytes());
+        }
+        for (c, v) in counters.remainder().iter().zip(into_words.into_remainder()) {
+            *v = c.load(Ordering::Relaxed);
+        }
+    }
+
+    /// Overwrite the counters with the values in `from`, previously saved with
+    /// [`save_checkpoint`][crate::Counters::save_checkpoint].
+    ///
+    /// The copy is done a word at a time, with relaxed stores. Like
+    /// [`reset`][crate::Counters::reset], it is racy, but not unsafe, with
+    /// respect to a concurrent consumer.
+    pub fn restore_checkpoint(&self, from: &[u8; N]) {
+        let mut counters = self.atomics().chunks_exact(WORD);
+        let mut from_words = from.chunks_exact(WORD);
+        for (c, v) in (&mut counters).zip(&mut from_words) {
+            let word = usize::from_ne_bytes(v.try_into().unwrap());
+            store_word(c, word);
+        }
+        for (c, v) in counters.remainder().iter().zip(from_words.remainder()) {
+            c.store(*v, Ordering::Relaxed);
+        }
+    }
+
+    /// Check whether these counters contain any coverage not yet recorded in
+    /// `virgin`, and record it.
+    ///
+    /// This is the equivalent of AFL's `has_new_bits`. `virgin` is a map of
+    /// coverage that has never been seen, and should initially be all `0xFF`.
+    /// Every bit that is set in a counter is cleared in the corresponding
+    /// `virgin` byte, and if any of those bits were previously set in `virgin`,
+    /// this returns `true`.
+    ///
+    /// Typically, the counters are [classified][crate::Counters::classify]
+    /// before being compared against the virgin map, but that is up to the
+    /// caller.
+    ///
+    /// The maps are compared a word at a time, skipping over words of counters
+    /// that are all zero. When built with `--cfg sancov_nightly` on a nightly
+    /// compiler, the comparison uses `core::simd` instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<4096> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// let mut virgin = [0xFF; 4096];
+    /// assert!(!COUNTERS.has_new_bits(&mut virgin));
+    ///
+    /// COUNTERS[42].increment();
+    /// assert!(COUNTERS.has_new_bits(&mut virgin));
+    ///
+    /// // Already recorded in the virgin map, so not new anymore.
+    /// assert!(!COUNTERS.has_new_bits(&mut virgin));
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    pub fn has_new_bits(&self, virgin: &mut [u8; N]) -> bool {
+        let counters: &[AtomicU8] = self.atomics();
+        let virgin: &mut [u8] = virgin;
+
+        #[cfg(sancov_nightly)]
+        let (counters, virgin, mut new) = has_new_bits_simd(counters, virgin);
+        #[cfg(not(sancov_nightly))]
+        let mut new = false;
+
+        let mut counter_chunks = counters.chunks_exact(WORD);
+        let mut virgin_chunks = virgin.chunks_exact_mut(WORD);
+        for (c, v) in (&mut counter_chunks).zip(&mut virgin_chunks) {
+            let c = load_word(c);
+            if c == 0 {
+                continue;
+            }
+            let mut word = usize::from_ne_bytes(v.try_into().unwrap());
+            if c & word != 0 {
+                new = true;
+                word &= !c;
+                v.copy_from_slice(&word.to_ne_bytes());
+            }
+        }
+        for (c, v) in counter_chunks
+            .remainder()
+            .iter()
+            .zip(virgin_chunks.into_remainder())
+        {
+            let c = c.load(Ordering::Relaxed);
+            if c & *v != 0 {
+                new = true;
+                *v &= !c;
+            }
+        }
+        new
+    }
+
+    /// Record the classified coverage in these counters in `virgin`, and count
+    /// the counters that contributed anything new.
+    ///
+    /// This is the classified counterpart of
+    /// [`has_new_bits`][crate::Counters::has_new_bits], and takes the same
+    /// virgin map, which should initially be all `0xFF`. Each non-zero counter
+    /// is first [classified][crate::Counters::classify] into its bucket, so the
+    /// comparison is always classified, never raw. If that bucket's bit is
+    /// still set in the counter's `virgin` byte, the bit is cleared and the
+    /// counter counts as new. So the result is the number of counters that
+    /// were hit for the first time, or whose hit count moved into a bucket
+    /// they had not reached before, and an input is worth keeping exactly
+    /// when it is non-zero.
+    ///
+    /// The maps are compared a word at a time, skipping over words of counters
+    /// that are all zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<4096> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// let mut virgin = [0xFF; 4096];
+    /// COUNTERS[1].increment();
+    /// COUNTERS[2].increment();
+    /// assert_eq!(COUNTERS.update_virgin(&mut virgin), 2);
+    /// assert_eq!(COUNTERS.update_virgin(&mut virgin), 0);
+    ///
+    /// // 2 hits is a new bucket for counter 1.
+    /// COUNTERS[1].increment();
+    /// assert_eq!(COUNTERS.update_virgin(&mut virgin), 1);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    pub fn update_virgin(&self, virgin: &mut [u8; N]) -> usize {
+        #[inline]
+        fn update(c: &AtomicU8, v: &mut u8) -> usize {
+            let bucket = COUNT_CLASS_LOOKUP[c.load(Ordering::Relaxed) as usize];
+            if bucket & *v != 0 {
+                *v &= !bucket;
+                1
+            } else {
+                0
+            }
+        }
+
+        let mut count = 0;
+        let mut counter_chunks = self.atomics().chunks_exact(WORD);
+        let mut virgin_chunks = virgin.chunks_exact_mut(WORD);
+        for (c, v) in (&mut counter_chunks).zip(&mut virgin_chunks) {
+            if load_word(c) == 0 {
+                continue;
+            }
+            for (c, v) in c.iter().zip(v) {
+                count += update(c, v);
+            }
+        }
+        for (c, v) in counter_chunks
+            .remainder()
+            .iter()
+            .zip(virgin_chunks.into_remainder())
+        {
+            count += update(c, v);
+        }
+        count
+    }
+
+    /// Count the counters that are non-zero, but whose corresponding byte in
+    /// `baseline` is zero.
+    ///
+    /// This measures how much new coverage these counters contain relative to
+    /// `baseline`, and is useful for scoring inputs during corpus
+    /// minimization.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// let counters = Counters::<4>::new();
+    /// counters[0].increment();
+    /// counters[1].increment();
+    ///
+    /// let baseline = [1, 0, 0, 0];
+    /// assert_eq!(counters.new_bits_count(&baseline), 1);
+    /// ```
+    pub fn new_bits_count(&self, baseline: &[u8; N]) -> usize {
+        let mut counter_chunks = self.atomics().chunks_exact(WORD);
+        let mut baseline_chunks = baseline.chunks_exact(WORD);
+        let mut count = 0;
+        for (c, b) in (&mut counter_chunks).zip(&mut baseline_chunks) {
+            let c = load_word(c);
+            if c == 0 {
+                continue;
+            }
+            let b = usize::from_ne_bytes(b.try_into().unwrap());
+            count += (nonzero_mask(c) & !nonzero_mask(b)).count_ones() as usize;
+        }
+        count
+            + counter_chunks
+                .remainder()
+                .iter()
+                .zip(baseline_chunks.remainder())
+                .filter(|(c, b)| c.load(Ordering::Relaxed) != 0 && **b == 0)
+                .count()
+    }
+
+    /// Write a bitset of the counters that differ from `baseline` into `out`.
+    ///
+    /// Bit `i % 64` of `out[i / 64]` is set exactly when `self[i] !=
+    /// baseline[i]`. The counters are compared a word at a time, and runs of
+    /// unchanged counters cost one comparison per word. Bitsets from several
+    /// inputs can then be combined with plain bitwise operations, e.g. OR-ed
+    /// together to find every edge that any of them changed.
+    ///
+    /// The first `N.div_ceil(64)` words of `out` are overwritten, with the bits
+    /// past `N` in the last of them cleared, and any further words are left
+    /// untouched.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// let counters = Counters::<100>::new();
+    /// let baseline = [0; 100];
+    /// counters[3].increment();
+    /// counters[99].increment();
+    ///
+    /// let mut changed = [0u64; 2];
+    /// counters.changed_bits(&baseline, &mut changed);
+    /// assert_eq!(changed, [1 << 3, 1 << 35]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `out.len() < N.div_ceil(64)`.
+    pub fn changed_bits(&self, baseline: &[u8; N], out: &mut [u64]) {
+        let out = &mut out[..N.div_ceil(64)];
+        out.fill(0);
+        let mut set = |i: usize| out[i / 64] |= 1 << (i % 64);
+
+        let mut counter_chunks = self.atomics().chunks_exact(WORD);
+        let mut baseline_chunks = baseline.chunks_exact(WORD);
+        let mut i = 0;
+        for (c, b) in (&mut counter_chunks).zip(&mut baseline_chunks) {
+            let diff = load_word(c) ^ usize::from_ne_bytes(b.try_into().unwrap());
+            if diff != 0 {
+                for (j, byte) in diff.to_ne_bytes().iter().enumerate() {
+                    if *byte != 0 {
+                        set(i + j);
+                    }
+                }
+            }
+            i += WORD;
+        }
+        for (c, b) in counter_chunks
+            .remainder()
+            .iter()
+            .zip(baseline_chunks.remainder())
+        {
+            if c.load(Ordering::Relaxed) != *b {
+                set(i);
+            }
+            i += 1;
+        }
+    }
+
+    /// Count the counters whose values differ from the corresponding byte in
+    /// `other`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// let counters = Counters::<4>::new();
+    /// counters[0].increment();
+    /// counters[1].increment();
+    ///
+    /// assert_eq!(counters.distance(&[1, 0, 0, 7]), 2);
+    /// ```
+    pub fn distance(&self, other: &[u8; N]) -> usize {
+        let mut counter_chunks = self.atomics().chunks_exact(WORD);
+        let mut other_chunks = other.chunks_exact(WORD);
+        let mut count = 0;
+        for (c, o) in (&mut counter_chunks).zip(&mut other_chunks) {
+            let c = load_word(c);
+            let o = usize::from_ne_bytes(o.try_into().unwrap());
+            count += nonzero_bytes(c ^ o);
+        }
+        count
+            + counter_chunks
+                .remainder()
+                .iter()
+                .zip(other_chunks.remainder())
+                .filter(|(c, o)| c.load(Ordering::Relaxed) != **o)
+                .count()
+    }
+
+    /// Write the AFL-style bucketed value of each counter into `out`.
+    ///
+    /// Each counter's value is mapped through
+    /// [`COUNT_CLASS_LOOKUP`][crate::COUNT_CLASS_LOOKUP], which collapses raw
+    /// hit counts into logarithmic buckets. Comparing classified maps, rather
+    /// than raw ones, avoids treating every small change in a loop's iteration
+    /// count as new coverage.
+    ///
+    /// The live counters are not modified.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `out.len() != N`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<4> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// for _ in 0..5 {
+    ///     COUNTERS[1].increment();
+    /// }
+    ///
+    /// let mut classified = [0; 4];
+    /// COUNTERS.classify(&mut classified);
+    /// assert_eq!(classified, [0, 8, 0, 0]);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    pub fn classify(&self, out: &mut [u8]) {
+        assert_eq!(out.len(), N);
+        for (o, c) in out.iter_mut().zip(self.atomics()) {
+            *o = COUNT_CLASS_LOOKUP[c.load(Ordering::Relaxed) as usize];
+        }
+    }
+
+    /// Iterate over the indices of counters that are zero, i.e. that have
+    /// never fired since they were created or last reset.
+    ///
+    /// This complements [`count_nonzero`][crate::Counters::count_nonzero].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<4> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// COUNTERS[1].increment();
+    /// assert!(COUNTERS.virgin_indices().eq([0, 2, 3]));
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    pub fn virgin_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.atomics()
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.load(Ordering::Relaxed) == 0)
+            .map(|(i, _)| i)
+    }
+
+    #[inline]
+    pub(crate) fn atomics(&self) -> &[AtomicU8; N] {
+        unsafe {
+            let ptr: *mut [u8; N] = self.0.get();
+            let ptr: *const [AtomicU8; N] = ptr as _;
+            &*ptr
+        }
+    }
+
+    /// Increment the counter at index `self.hash_index(x)`.
+    ///
+    /// This allows you to map an unbounded number of logical counters down onto
+    /// a bounded number of actual counters.
+    ///
+    /// Useful when:
+    ///
+    /// 1. You have a dynamic number of logical counters, but you have to choose
+    ///    a static number of actual counters at initialization time when
+    ///    registering the counters because of `SanitizerCoverage` API
+    ///    constraints. Note that using this method is preferable to doing
+    ///    `counters[i % counters.len()].increment()` in this situation, since
+    ///    that can suffer from harmonics.
+    ///
+    /// 2. You have very many and very sparsely incremented logical counters. So
+    ///    many logical counters that the performance of the consumer iterating
+    ///    over them all would be severely impacted if you had that many actual
+    ///    counters.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<16> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// // Increment the "i^th" counter, where `i` is larger than how many
+    /// // actual counters we have.
+    /// COUNTERS.hash_increment(&69);
+    /// COUNTERS.hash_increment(&420);
+    ///
+    /// // Or increment counters named by anything that is hashable!
+    /// COUNTERS.hash_increment("wheelies");
+    /// COUNTERS.hash_increment("won't");
+    /// COUNTERS.hash_increment("pop");
+    /// COUNTERS.hash_increment("themselves!");
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    #[inline]
+    #[cfg(feature = "hash_increment")]
+    #[cfg_attr(sancov_disabled, allow(unused_variables))]
+    pub fn hash_increment<T>(&self, x: &T)
+    where
+        T: ?Sized + core::hash::Hash,
+    {
+        enabled! {
+            let i = self.hash_index(x);
+            self[i].increment();
+        }
+    }
+
+    /// Increment the counter selected by an already-computed `hash`.
+    ///
+    /// This is [`hash_increment`][crate::Counters::hash_increment] without the
+    /// hashing step, for callers that already have a 64-bit hash of their key.
+    /// `hash` is reduced to an index in the same way as the hash that
+    /// `hash_increment` computes, so the same `hash` always selects the same
+    /// counter.
+    ///
+    /// The caller is responsible for the quality of the hash. The reduction
+    /// selects a counter using the high bits of `hash`, so hashes that differ
+    /// only in their low bits, such as small integers used directly, all land
+    /// on the same counter.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<16> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// // A hash that was computed for some other purpose.
+    /// let hash: u64 = 0x9e37_79b9_7f4a_7c15;
+    /// COUNTERS.hash_increment_raw(hash);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    #[inline]
+    #[cfg(feature = "hash_increment")]
+    #[cfg_attr(sancov_disabled, allow(unused_variables))]
+    pub fn hash_increment_raw(&self, hash: u64) {
+        enabled! {
+            self[reduce(hash, N)].increment();
+        }
+    }
+
+    /// Get the index of the counter that
+    /// [`hash_increment`][crate::Counters::hash_increment] would increment for
+    /// `x`, without incrementing it.
+    ///
+    /// This is useful for debugging collisions between logical counters.
+    ///
+    /// The index is computed from the 64-bit `fxhash` of `x`, regardless of the
+    /// target's pointer width, so it is the same on 32- and 64-bit targets. It
+    /// is reduced to `0..N` with a multiply-shift rather than a remainder,
+    /// which uses the well-mixed high bits of the hash instead of its weak low
+    /// bits, and avoids a division. When `N` is a power of two, the reduction
+    /// is a single shift, so power-of-two sizes are the fastest.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<16> = Counters::new();
+    ///
+    /// let i = COUNTERS.hash_index("wheelies");
+    /// let j = COUNTERS.hash_index("won't");
+    /// if i == j {
+    ///     println!("collision at counter {i}");
+    /// }
+    /// ```
+    #[inline]
+    #[cfg(feature = "hash_increment")]
+    pub fn hash_index<T>(&self, x: &T) -> usize
+    where
+        T: ?Sized + core::hash::Hash,
+    {
+        reduce(fxhash::hash64(x), N)
+    }
+
+    /// Estimate how many distinct counters a set of keys would occupy when
+    /// passed to [`hash_increment`][crate::Counters::hash_increment].
+    ///
+    /// Returns `(distinct_slots_used, total_keys)`. If the keys are distinct,
+    /// `total_keys - distinct_slots_used` is the number of collisions, and
+    /// `distinct_slots_used / N` is the load factor, which is useful for
+    /// choosing `N` empirically.
+    ///
+    /// This does not touch any live counters. It tracks the occupied counters
+    /// in a bitset of `N` bits, which lives on the stack for `N` up to
+    /// `32768`, and on the heap for larger `N`. Without the `alloc` feature,
+    /// larger `N` is a compile-time error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// let keys = (0..1000).map(|i| format!("key-{i}"));
+    /// let (used, total) = Counters::<4096>::estimate_collisions(keys);
+    /// assert_eq!(total, 1000);
+    /// assert!(used <= total);
+    /// println!("{} collisions", total - used);
+    /// ```
+    #[cfg(feature = "hash_increment")]
+    pub fn estimate_collisions<T, I>(keys: I) -> (usize, usize)
+    where
+        T: core::hash::Hash,
+        I: IntoIterator<Item = T>,
+    {
+        const STACK_WORDS: usize = 512;
+        let words = N.div_ceil(64);
+        let mut stack = [0u64; STACK_WORDS];
+        #[cfg(feature = "alloc")]
+        let mut heap;
+        let seen: &mut [u64] = if words <= STACK_WORDS {
+            &mut stack[..words]
+        } else {
+            #[cfg(feature = "alloc")]
+            {
+                heap = alloc::vec![0u64; words];
+                &mut heap
+            }
+            #[cfg(not(feature = "alloc"))]
+            {
+                const {
+                    assert!(
+                        N <= STACK_WORDS * 64,
+                        "`estimate_collisions` requires the `alloc` feature for `N > 32768`"
+                    )
+                };
+                unreachable!()
+            }
+        };
+
+        let mut used = 0;
+        let mut total = 0;
+        for key in keys {
+            let i = reduce(fxhash::hash64(&key), N);
+            let bit = 1 << (i % 64);
+            if seen[i / 64] & bit == 0 {
+                seen[i / 64] |= bit;
+                used += 1;
+            }
+            total += 1;
+        }
+        (used, total)
+    }
+
+    /// Increment the counter at index `self.hash_index(x)` by `n`.
+    ///
+    /// This is like [`hash_increment`][crate::Counters::hash_increment] but
+    /// uses [`Counter::increment_by`][crate::Counter::increment_by].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<16> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// // This loop ran 10 times.
+    /// COUNTERS.hash_increment_by("loop", 10);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    #[inline]
+    #[cfg(feature = "hash_increment")]
+    #[cfg_attr(sancov_disabled, allow(unused_variables))]
+    pub fn hash_increment_by<T>(&self, x: &T, n: u8)
+    where
+        T: ?Sized + core::hash::Hash,
+    {
+        enabled! {
+            let i = self.hash_index(x);
+            self[i].increment_by(n);
+        }
+    }
+
+    /// Call [`hash_increment`][crate::Counters::hash_increment] for each key
+    /// in `keys`.
+    ///
+    /// Consecutive keys that map onto the same counter are coalesced into a
+    /// single [`Counter::increment_by`][crate::Counter::increment_by], which
+    /// has the same effect as incrementing that counter once per key.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<16> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// // Increment one counter per token.
+    /// COUNTERS.hash_increment_all("let x = x + 1 ;".split(' '));
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    #[cfg(feature = "hash_increment")]
+    #[cfg_attr(sancov_disabled, allow(unused_variables))]
+    pub fn hash_increment_all<T, I>(&self, keys: I)
+    where
+        T: core::hash::Hash,
+        I: IntoIterator<Item = T>,
+    {
+        enabled! {
+            let mut pending: Option<(usize, u8)> = None;
+            for key in keys {
+                let i = self.hash_index(&key);
+                pending = match pending {
+                    Some((j, n)) if j == i && n < u8::MAX => Some((j, n + 1)),
+                    Some((j, n)) => {
+                        self[j].increment_by(n);
+                        Some((i, 1))
+                    }
+                    None => Some((i, 1)),
+                };
+            }
+            if let Some((j, n)) = pending {
+                self[j].increment_by(n);
+            }
+        }
+    }
+
+    /// Increment the counter at index `self.hash_index(x)`, saturating at
+    /// `u8::MAX`.
+    ///
+    /// This is like [`hash_increment`][crate::Counters::hash_increment] but
+    /// uses [`Counter::saturating_increment`][crate::Counter::saturating_increment].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<16> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// COUNTERS.hash_saturating_increment("wheelies");
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    #[inline]
+    #[cfg(feature = "hash_increment")]
+    #[cfg_attr(sancov_disabled, allow(unused_variables))]
+    pub fn hash_saturating_increment<T>(&self, x: &T)
+    where
+        T: ?Sized + core::hash::Hash,
+    {
+        enabled! {
+            let i = self.hash_index(x);
+            self[i].saturating_increment();
+        }
+    }
+
+    /// Increment the counter for the type `T`.
+    ///
+    /// This hashes [`TypeId::of::<T>()`][core::any::TypeId::of] with
+    /// [`hash_increment`][crate::Counters::hash_increment], so that each
+    /// concrete type gets its own counter without any bookkeeping. Calling
+    /// this from generic code gives coverage feedback on which instantiations
+    /// were actually executed.
+    ///
+    /// Like any use of `hash_increment`, distinct types can collide onto the
+    /// same counter, in which case they are indistinguishable; choose `N`
+    /// comfortably larger than the number of types you expect. `TypeId`s are
+    /// only stable within a single build, so which types collide, and the
+    /// index each type maps to, can change whenever the program is recompiled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<256> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// fn parse<T: 'static + std::str::FromStr>(s: &str) -> Option<T> {
+    ///     COUNTERS.increment_type::<T>();
+    ///     s.parse().ok()
+    /// }
+    ///
+    /// parse::<u32>("42");
+    /// parse::<f64>("4.2");
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    #[inline]
+    #[cfg(feature = "hash_increment")]
+    pub fn increment_type<T>(&self)
+    where
+        T: ?Sized + 'static,
+    {
+        self.hash_increment(&core::any::TypeId::of::<T>());
+    }
+
+    /// Increment the counter selected by the hash of `x`, computed with the
+    /// given `hasher`.
+    ///
+    /// This is like `hash_increment`, but lets you choose the hash function,
+    /// and does not require the `hash_increment` feature. The hash is reduced
+    /// to an index in the same way, using its high bits.
+    ///
+    /// The choice of hasher is a tradeoff between speed and collision
+    /// resistance. A fast hasher like `fxhash` does very little mixing, which
+    /// is fine for most keys, but can map structured keys, such as integers
+    /// that differ only in their high bits, onto the same few counters. When
+    /// keys are sparse within a large key space, a hasher with better
+    /// avalanche behavior, like `ahash` or `std`'s SipHash, spreads them more
+    /// evenly across the counters at the cost of some speed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    /// use std::collections::hash_map::DefaultHasher;
+    ///
+    /// static COUNTERS: Counters<16> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// COUNTERS.hash_increment_with("wheelies", DefaultHasher::new());
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    #[inline]
+    #[cfg_attr(sancov_disabled, allow(unused_mut, unused_variables))]
+    pub fn hash_increment_with<T, H>(&self, x: &T, mut hasher: H)
+    where
+        T: ?Sized + core::hash::Hash,
+        H: core::hash::Hasher,
+    {
+        enabled! {
+            x.hash(&mut hasher);
+            let i = reduce(hasher.finish(), N);
+            self[i].increment();
+        }
+    }
+}
+
+/// The error returned by [`Counters::try_register`][crate::Counters::try_register]
+/// when no `SanitizerCoverage` consumer is linked in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NoConsumer;
+
+impl core::fmt::Display for NoConsumer {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("no `SanitizerCoverage` consumer is linked in")
+    }
+}
+
+impl core::error::Error for NoConsumer {}
+
+/// The successor of a counter value under the "NeverZero" increment policy.
+///
+/// This is the single definition of the transition that
+/// [`Counter::increment`][crate::Counter::increment] and every other
+/// "NeverZero" increment in this crate performs: `count + 1`, except that
+/// `255` is followed by `1` rather than `0`, because the overflow carry is
+/// added back in.
+///
+/// # Example
+///
+/// ```
+/// use sancov::neverzero_next;
+///
+/// assert_eq!(neverzero_next(0), 1);
+/// assert_eq!(neverzero_next(41), 42);
+/// assert_eq!(neverzero_next(255), 1);
+/// ```
+#[inline]
+pub const fn neverzero_next(count: u8) -> u8 {
+    let (next, overflowed) = count.overflowing_add(1);
+    next + (overflowed as u8)
+}
+
+/// A lookup table of each counter value's successor under the "NeverZero"
+/// increment policy.
+///
+/// `NEVER_ZERO_NEXT[x]` is [`neverzero_next(x)`][crate::neverzero_next].
+///
+/// See [`Counter::increment_table`][crate::Counter::increment_table].
+pub const NEVER_ZERO_NEXT: [u8; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = neverzero_next(i as u8);
+        i += 1;
+    }
+    table
+};
+
+/// AFL++'s lookup table for bucketing raw hit counts.
+///
+/// The buckets are `0`, `1`, `2`, `3`, `4..=7`, `8..=15`, `16..=31`, `32..=127`,
+/// and `128..=255`, which are respectively mapped to `0`, `1`, `2`, `4`, `8`,
+/// `16`, `32`, `64`, and `128`.
+///
+/// See [`Counters::classify`][crate::Counters::classify].
+pub const COUNT_CLASS_LOOKUP: [u8; 256] = {
+    let mut table = [0; 256];
+    let mut i = 1;
+    while i < 256 {
+        table[i] = match i {
+            1 => 1,
+            2 => 2,
+            3 => 4,
+            4..=7 => 8,
+            8..=15 => 16,
+            16..=31 => 32,
+            32..=127 => 64,
+            _ => 128,
+        };
+        i += 1;
+    }
+    table
+};
+
+const WORD: usize = core::mem::size_of::<usize>();
+
+/// Store zero to every byte in `bytes`, with relaxed ordering.
+fn zero(bytes: &[AtomicU8]) {
+    for byte in bytes {
+        byte.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Reduce a 64-bit hash to an index in `0..n`, with Lemire's multiply-shift.
+///
+/// This maps `hash` to `floor(hash * n / 2^64)`, which is as uniform as
+/// `hash % n`, but depends on the high bits of the hash, and needs no division.
+///
+/// When `n` is a power of two, `2^k`, that is just the top `k` bits of the
+/// hash, which is a single shift. `n` is a constant at every call site that
+/// matters, so the check is resolved at compile time. The shift is used rather
+/// than masking with `n - 1`, which would select the low bits: those are the
+/// bits that `fxhash` mixes poorly, and masking would also select different
+/// counters than other sizes do.
+#[inline]
+#[cfg_attr(sancov_disabled, allow(dead_code))]
+fn reduce(hash: u64, n: usize) -> usize {
+    if n.is_power_of_two() {
+        reduce_pow2(hash, n)
+    } else {
+        ((hash as u128 * n as u128) >> 64) as usize
+    }
+}
+
+/// The power-of-two case of [`reduce`].
+#[inline]
+#[cfg_attr(sancov_disabled, allow(dead_code))]
+fn reduce_pow2(hash: u64, n: usize) -> usize {
+    debug_assert!(n.is_power_of_two());
+    // For `n == 1`, the shift would be by 64, and the index is always zero.
+    hash.checked_shr(64 - n.trailing_zeros()).unwrap_or(0) as usize
+}
+
+/// Load `WORD` bytes with relaxed ordering into a single word.
+#[inline]
+fn load_word(chunk: &[AtomicU8]) -> usize {
+    let mut bytes = [0; WORD];
+    for (b, a) in bytes.iter_mut().zip(chunk) {
+        *b = a.load(Ordering::Relaxed);
+    }
+    usize::from_ne_bytes(bytes)
+}
+
+/// Store a single word into `WORD` bytes with relaxed ordering.
+#[inline]
+fn store_word(chunk: &[AtomicU8], word: usize) {
+    for (a, b) in chunk.iter().zip(word.to_ne_bytes()) {
+        a.store(b, Ordering::Relaxed);
+    }
+}
+
+/// The SIMD portion of `Counters::has_new_bits`.
+///
+/// Returns the unprocessed tails of `counters` and `virgin`, and whether any
+/// new bits were found.
+#[cfg(sancov_nightly)]
+fn has_new_bits_simd<'a, 'b>(
+    counters: &'a [AtomicU8],
+    virgin: &'b mut [u8],
+) -> (&'a [AtomicU8], &'b mut [u8], bool) {
+    use core::simd::prelude::*;
+
+    const LANES: usize = 32;
+
+    let mut new = false;
+    let mut counter_chunks = counters.chunks_exact(LANES);
+    let mut virgin_chunks = virgin.chunks_exact_mut(LANES);
+    for (c, v) in (&mut counter_chunks).zip(&mut virgin_chunks) {
+        let mut bytes = [0; LANES];
+        for (b, a) in bytes.iter_mut().zip(c) {
+            *b = a.load(Ordering::Relaxed);
+        }
+        let c = u8x32::from_array(bytes);
+        if c == u8x32::splat(0) {
+            continue;
+        }
+        let word = u8x32::from_slice(v);
+        if (c & word) != u8x32::splat(0) {
+            new = true;
+            (word & !c).copy_to_slice(v);
+        }
+    }
+    (
+        counter_chunks.remainder(),
+        virgin_chunks.into_remainder(),
+        new,
+    )
+}
+
+/// Get a mask with the low bit set in each non-zero byte of a word.
+#[inline]
+fn nonzero_mask(word: usize) -> usize {
+    let ones = usize::from_ne_bytes([1; WORD]);
+    let mut x = word | (word >> 4);
+    x |= x >> 2;
+    x |= x >> 1;
+    x & ones
+}
+
+/// Count the non-zero bytes within a word.
+#[inline]
+fn nonzero_bytes(word: usize) -> usize {
+    nonzero_mask(word).count_ones() as usize
+}
+
+impl<const N: usize> Index<usize> for Counters<N> {
+    type Output = Counter;
+
+    #[inline]
+    fn index(&self, index: usize) -> &Self::Output {
+        assert!(index < N);
+        &self.as_array()[index]
+    }
+}
+
+/// Get a subslice of the counters.
+///
+/// The returned counters are the same counters that the `SanitizerCoverage`
+/// consumer observes.
+///
+/// # Panics
+///
+/// Panics if the range is out of bounds.
+///
+/// # Example
+///
+/// ```
+/// use sancov::{Counter, Counters};
+///
+/// static COUNTERS: Counters<4096> = Counters::new();
+/// COUNTERS.register();
+///
+/// fn parser_coverage(counters: &[Counter]) {
+///     counters[7].increment();
+/// }
+///
+/// // Counters 0..1024 belong to the parser.
+/// parser_coverage(&COUNTERS[0..1024]);
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+/// ```
+impl<const N: usize> Index<Range<usize>> for Counters<N> {
+    type Output = [Counter];
+
+    #[inline]
+    fn index(&self, range: Range<usize>) -> &Self::Output {
+        &self.as_array()[range]
+    }
+}
+
+// There is intentionally no `AsRef<[u8]>` implementation: a `&[u8]` promises
+// that the bytes will not change while it is borrowed, which the counters
+// cannot guarantee, since they may be incremented at any time. Callers can use
+// the `unsafe` `as_slice` method instead, or take a `Snapshot` and borrow its
+// bytes.
+
+/// Unlike most `AsRef` borrows, this one is not stable: the counters may still
+/// be incremented, from this thread or any other, while it is alive, so two
+/// reads through it can disagree. Take a [`Snapshot`][crate::Snapshot] for a
+/// copy that does not change.
+impl<const N: usize> AsRef<[Counter; N]> for Counters<N> {
+    #[inline]
+    fn as_ref(&self) -> &[Counter; N] {
+        self.as_array()
+    }
+}
+
+/// Like the `AsRef<[Counter; N]>` implementation, the borrow is not stable:
+/// the counters may still be incremented while it is alive.
+impl<const N: usize> AsRef<[Counter]> for Counters<N> {
+    #[inline]
+    fn as_ref(&self) -> &[Counter] {
+        self.as_array()
+    }
+}
+
+impl<const N: usize> Default for Counters<N> {
+    /// Equivalent to [`Counters::new`][crate::Counters::new].
+    ///
+    /// # Compile-time errors
+    ///
+    /// Like `Counters::new`, `N` must not be zero.
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for Counters<N> {
+    /// Formats a compact summary of the counters, rather than every counter's
+    /// value.
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<4096> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// COUNTERS[42].increment();
+    /// assert_eq!(format!("{COUNTERS:?}"), "Counters<4096> { nonzero: 1 }");
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Counters<{N}> {{ nonzero: {} }}", self.count_nonzero())
+    }
+}
+
+impl<const N: usize> core::fmt::Display for Counters<N> {
+    /// Formats a one-line coverage summary, for periodic status logs: how many
+    /// counters are non-zero, as a count and a percentage, and how many are
+    /// saturated.
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<4096> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// for i in 0..412 {
+    ///     COUNTERS[i].increment();
+    /// }
+    /// COUNTERS[7].increment_by(254);
+    /// assert_eq!(COUNTERS.to_string(), "cov 412/4096 (10.1%) sat 1");
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut nonzero = 0;
+        let mut saturated = 0;
+        for c in self.atomics() {
+            match c.load(Ordering::Relaxed) {
+                0 => {}
+                u8::MAX => {
+                    nonzero += 1;
+                    saturated += 1;
+                }
+                _ => nonzero += 1,
+            }
+        }
+        let percent = nonzero as f64 * 100.0 / N as f64;
+        write!(f, "cov {nonzero}/{N} ({percent:.1}%) sat {saturated}")
+    }
+}
+
+/// Compares the counters' current values.
+///
+/// This is inherently racy if either set of counters is being concurrently
+/// modified. For deterministic results, compare
+/// [`Snapshot`][crate::Snapshot]s instead.
+///
+/// # Example
+///
+/// ```
+/// use sancov::Counters;
+///
+/// let a = Counters::<16>::new();
+/// let b = Counters::<16>::new();
+/// a[3].increment();
+/// b[3].increment();
+/// assert_eq!(a, b);
+///
+/// b[4].increment();
+/// assert_ne!(a, b);
+/// ```
+impl<const N: usize> PartialEq for Counters<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.atomics()
+            .iter()
+            .zip(other.atomics())
+            .all(|(a, b)| a.load(Ordering::Relaxed) == b.load(Ordering::Relaxed))
+    }
+}
+
+impl<const N: usize> Eq for Counters<N> {}
+
+/// Hashes the counters' current values, for example to deduplicate inputs by
+/// the coverage they produce.
+///
+/// The values are fed to the hasher in chunks, via relaxed loads, without
+/// copying the whole map first. Like equality, this is inherently racy if the
+/// counters are being concurrently modified, and a hash taken while they change
+/// may not match any state they were actually in. For deterministic results,
+/// hash a [`Snapshot`][crate::Snapshot] instead.
+///
+/// # Example
+///
+/// ```
+/// use sancov::Counters;
+/// use std::collections::HashSet;
+/// use std::hash::{BuildHasher, RandomState};
+///
+/// let a = Counters::<16>::new();
+/// let b = Counters::<16>::new();
+/// a[3].increment();
+/// b[3].increment();
+///
+/// let state = RandomState::new();
+/// assert_eq!(state.hash_one(&a), state.hash_one(&b));
+///
+/// // Prefer snapshots as hash map keys.
+/// let mut seen = HashSet::new();
+/// assert!(seen.insert(a.snapshot()));
+/// assert!(!seen.insert(b.snapshot()));
+/// ```
+impl<const N: usize> core::hash::Hash for Counters<N> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        state.write_usize(N);
+        let mut buf = [0u8; 64];
+        for chunk in self.atomics().chunks(buf.len()) {
+            for (b, c) in buf.iter_mut().zip(chunk) {
+                *b = c.load(Ordering::Relaxed);
+            }
+            state.write(&buf[..chunk.len()]);
+        }
+    }
+}
+
+impl<'a, const N: usize> IntoIterator for &'a Counters<N> {
+    type Item = &'a Counter;
+    type IntoIter = core::slice::Iter<'a, Counter>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A single 8-bit counter.
+///
+/// It can be incremented, with one of three overflow policies:
+///
+/// * [`increment`][crate::Counter::increment] uses AFL++'s "NeverZero"
+///   policy, where `255` wraps around to `1`. This is the recommended default.
+///
+/// * [`saturating_increment`][crate::Counter::saturating_increment] stays at
+///   `255`.
+///
+/// * [`wrapping_increment`][crate::Counter::wrapping_increment] wraps around to
+///   `0`, like clang's own inline 8-bit counters.
+///
 /// It has the same representation as a `u8`. You can rely on this fact and
 /// increment this counter from, for example, JIT code.
+///
+/// On targets without atomic byte operations (`target_has_atomic = "8"` is
+/// false), counters are backed by plain, non-atomic bytes instead, so that they
+/// can still be used on those targets. There, counters, and everything built
+/// on them, must only ever be accessed from a single thread.
 #[repr(transparent)]
 pub struct Counter(AtomicU8);
 
+impl core::fmt::Debug for Counter {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("Counter").field(&self.get()).finish()
+    }
+}
+
 impl Counter {
+    /// Get this counter's current value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<16> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// if COUNTERS[3].get() == 0 {
+    ///     println!("first time here!");
+    /// }
+    /// COUNTERS[3].increment();
+    /// assert_eq!(COUNTERS[3].get(), 1);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    #[inline]
+    pub fn get(&self) -> u8 {
+        self.0.load(Ordering::Relaxed)
+    }
+
     /// Increment this counter.
     ///
     /// This uses AFL++'s "NeverZero" approach, where we add the overflow carry
@@ -203,20 +2333,934 @@ impl Counter {
     /// See section 3.3 of [the AFL++
     /// paper](https://www.usenix.org/system/files/woot20-paper-fioraldi.pdf)
     /// for details.
+    ///
+    /// With the `contention_stats` feature, this is instead a lossless
+    /// compare-and-swap loop that counts its retries, so concurrent increments
+    /// are never lost, at some cost in speed; see
+    /// `Counters::contention_estimate`.
     #[inline]
     pub fn increment(&self) {
-        let count = self.0.load(Ordering::Relaxed);
-        let (count, overflowed) = count.overflowing_add(1);
-        self.0.store(count + (overflowed as u8), Ordering::Relaxed);
+        enabled! {
+            #[cfg(not(all(feature = "contention_stats", target_has_atomic = "64")))]
+            {
+                let count = self.0.load(Ordering::Relaxed);
+                self.0.store(neverzero_next(count), Ordering::Relaxed);
+            }
+            #[cfg(all(feature = "contention_stats", target_has_atomic = "64"))]
+            contention::increment(&self.0);
+        }
+    }
+
+    /// Increment this counter, using a lookup table rather than arithmetic.
+    ///
+    /// This looks the counter's next value up in
+    /// [`NEVER_ZERO_NEXT`][crate::NEVER_ZERO_NEXT], which is branch-free. It
+    /// produces exactly the same sequence of values as
+    /// [`increment`][crate::Counter::increment], and only exists so that the two
+    /// implementations can be benchmarked against each other.
+    #[inline]
+    pub fn increment_table(&self) {
+        enabled! {
+            let count = self.0.load(Ordering::Relaxed);
+            self.0.store(NEVER_ZERO_NEXT[count as usize], Ordering::Relaxed);
+        }
+    }
+
+    /// Increment this counter, using sequentially-consistent loads and stores.
+    ///
+    /// This has the same "NeverZero" semantics as
+    /// [`increment`][crate::Counter::increment], but participates in the
+    /// single total order of `SeqCst` operations, which is useful when another
+    /// thread must observe this increment in order relative to other
+    /// `SeqCst` operations. It is slower than `increment`, and is not
+    /// necessary for ordinary fuzzing feedback; see
+    /// [`Counters::barrier`][crate::Counters::barrier] for details.
+    ///
+    /// Note that, like `increment`, this is a separate load and store rather
+    /// than a single read-modify-write, so concurrent increments of the same
+    /// counter may be lost.
+    #[inline]
+    pub fn increment_seqcst(&self) {
+        enabled! {
+            let count = self.0.load(Ordering::SeqCst);
+            self.0.store(neverzero_next(count), Ordering::SeqCst);
+        }
+    }
+
+    /// Increment this counter, and return whether it was zero before.
+    ///
+    /// This uses the same "NeverZero" overflow policy as
+    /// [`increment`][crate::Counter::increment], but as a single atomic
+    /// read-modify-write rather than a separate load and store. So no
+    /// concurrent increments are lost, and exactly one thread sees `true` for
+    /// each transition from zero, i.e. at most once between resets. This makes
+    /// it suitable for lazily doing something once per newly-hit edge. It is
+    /// correspondingly slower than `increment`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<16> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// let mut new_edges = vec![];
+    /// for i in [3, 5, 3, 3] {
+    ///     if COUNTERS[i].increment_first() {
+    ///         new_edges.push(i);
+    ///     }
+    /// }
+    /// assert_eq!(new_edges, [3, 5]);
+    /// assert_eq!(COUNTERS[3].get(), 3);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    #[inline]
+    pub fn increment_first(&self) -> bool {
+        if cfg!(sancov_disabled) {
+            return false;
+        }
+        let mut count = self.0.load(Ordering::Relaxed);
+        loop {
+            let next = neverzero_next(count);
+            match self
+                .0
+                .compare_exchange(count, next, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                Ok(_) => return count == 0,
+                Err(actual) => count = actual,
+            }
+        }
+    }
+
+    /// Increment this counter by `n`.
+    ///
+    /// This uses the same "NeverZero" approach as
+    /// [`increment`][crate::Counter::increment]: if the addition overflows, the
+    /// carry is added back to the counter, so that it is never left at zero
+    /// after being incremented by a non-zero amount.
+    #[inline]
+    #[cfg_attr(sancov_disabled, allow(unused_variables))]
+    pub fn increment_by(&self, n: u8) {
+        enabled! {
+            let count = self.0.load(Ordering::Relaxed);
+            let (count, overflowed) = count.overflowing_add(n);
+            self.0.store(count + (overflowed as u8), Ordering::Relaxed);
+        }
+    }
+
+    /// Mark this counter as hit, by setting it to `1` if it is `0`.
+    ///
+    /// This is for breadth-only feedback, where the consumer only cares
+    /// whether each counter is non-zero. It is a relaxed load, plus a relaxed
+    /// store only the first time, so a hot edge that has already been marked
+    /// costs no stores at all. A counter that has already been incremented is
+    /// left as it is.
+    ///
+    /// A marked counter always [`classify`][crate::Counters::classify]s into
+    /// the `1` bucket, so mixing `mark` and `increment` on the same counter
+    /// makes hit counts unreliable; use one or the other for each counter.
+    #[inline]
+    pub fn mark(&self) {
+        enabled! {
+            if self.0.load(Ordering::Relaxed) == 0 {
+                self.0.store(1, Ordering::Relaxed);
+            }
+        }
     }
 
     /// Increment this counter, saturating at `u8::MAX`.
     pub fn saturating_increment(&self) {
-        let count = self.0.load(Ordering::Relaxed);
-        self.0.store(count.saturating_add(1), Ordering::Relaxed);
+        enabled! {
+            let count = self.0.load(Ordering::Relaxed);
+            self.0.store(count.saturating_add(1), Ordering::Relaxed);
+        }
+    }
+
+    /// Increment this counter, wrapping around from `u8::MAX` to zero.
+    ///
+    /// Note that wrapping around to zero makes a counter that has been hit a
+    /// multiple of 256 times indistinguishable from one that has never been
+    /// hit. Prefer [`increment`][crate::Counter::increment] unless you
+    /// specifically want these semantics.
+    #[inline]
+    pub fn wrapping_increment(&self) {
+        enabled! {
+            let count = self.0.load(Ordering::Relaxed);
+            self.0.store(count.wrapping_add(1), Ordering::Relaxed);
+        }
+    }
+
+    /// Reset this counter back to zero.
+    #[inline]
+    pub fn reset(&self) {
+        self.0.store(0, Ordering::Relaxed);
     }
 }
 
 #[cfg(test)]
 #[no_mangle]
+#[allow(missing_docs)]
 pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    fn increment_by_never_zero() {
+        let counter = Counter(AtomicU8::new(0));
+        counter.increment_by(255);
+        assert_eq!(counter.get(), 255);
+        counter.increment_by(1);
+        assert_eq!(counter.get(), 1);
+
+        let counter = Counter(AtomicU8::new(1));
+        counter.increment_by(255);
+        assert_eq!(counter.get(), 1);
+
+        let counter = Counter(AtomicU8::new(254));
+        counter.increment_by(255);
+        assert_eq!(counter.get(), 254);
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    fn classify_bucket_boundaries() {
+        let counters = Counters::<6>::new();
+        for (counter, n) in counters.iter().zip([3, 4, 15, 16, 127, 128]) {
+            counter.increment_by(n);
+        }
+        let mut out = [0; 6];
+        counters.classify(&mut out);
+        assert_eq!(out, [4, 8, 16, 32, 64, 128]);
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    fn has_new_bits_clears_virgin() {
+        let counters = Counters::<67>::new();
+        let mut virgin = [0xFF; 67];
+        assert!(!counters.has_new_bits(&mut virgin));
+
+        // One in a full word, and one in the remainder.
+        counters[9].increment_by(3);
+        counters[66].increment();
+        assert!(counters.has_new_bits(&mut virgin));
+        assert_eq!(virgin[9], !3);
+        assert_eq!(virgin[66], !1);
+        assert!(virgin
+            .iter()
+            .enumerate()
+            .all(|(i, v)| i == 9 || i == 66 || *v == 0xFF));
+        assert!(!counters.has_new_bits(&mut virgin));
+
+        // A new bit in an already-touched counter is new coverage.
+        counters[9].increment();
+        assert!(counters.has_new_bits(&mut virgin));
+        assert_eq!(virgin[9], !7);
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    #[cfg(feature = "hash_increment")]
+    fn hash_saturating_increment_stays_saturated() {
+        let counters = Counters::<16>::new();
+        for _ in 0..1000 {
+            counters.hash_saturating_increment("pop");
+        }
+        let i = counters.hash_index("pop");
+        assert_eq!(counters[i].get(), 255);
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    fn merge_from_is_union() {
+        let total = Counters::<64>::new();
+        let run = Counters::<64>::new();
+        total[1].increment_by(5);
+        total[2].increment_by(1);
+        run[2].increment_by(3);
+        run[40].increment_by(200);
+
+        total.merge_from(&run);
+        let values: [u8; 64] = total.snapshot().0;
+        let mut expected = [0; 64];
+        expected[1] = 5;
+        expected[2] = 3;
+        expected[40] = 200;
+        assert_eq!(values, expected);
+
+        total.merge_from_saturating(&run);
+        assert_eq!(total[1].get(), 5);
+        assert_eq!(total[2].get(), 6);
+        assert_eq!(total[40].get(), 255);
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    fn new_bits_count_and_distance() {
+        let counters = Counters::<19>::new();
+        let mut baseline = [0; 19];
+        assert_eq!(counters.new_bits_count(&baseline), 0);
+        assert_eq!(counters.distance(&baseline), 0);
+
+        for i in [0, 5, 8, 17, 18] {
+            counters[i].increment();
+        }
+        baseline[0] = 3;
+        baseline[9] = 1;
+        baseline[18] = 200;
+
+        // New in 5, 8, and 17.
+        assert_eq!(counters.new_bits_count(&baseline), 3);
+        // Differ in 0, 5, 8, 9, 17, and 18.
+        assert_eq!(counters.distance(&baseline), 6);
+        assert_eq!(counters.distance(&counters.snapshot().0), 0);
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    #[cfg(feature = "hash_increment")]
+    fn hash_increment_all_repeated_keys() {
+        let bulk = Counters::<16>::new();
+        let single = Counters::<16>::new();
+        let keys = [1, 1, 1, 2, 3, 3, 1, 2, 2, 2];
+        bulk.hash_increment_all(keys);
+        for key in keys {
+            single.hash_increment(&key);
+        }
+        assert!(bulk == single);
+
+        // Long runs of the same key wrap around like individual increments.
+        let bulk = Counters::<16>::new();
+        let single = Counters::<16>::new();
+        bulk.hash_increment_all(core::iter::repeat_n("pop", 600));
+        for _ in 0..600 {
+            single.hash_increment("pop");
+        }
+        assert!(bulk == single);
+        assert_eq!(bulk[bulk.hash_index("pop")].get(), 90);
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    #[cfg(feature = "ffi")]
+    fn ffi_increments() {
+        let counters = Counters::<16>::new();
+        let base = counters.as_array().as_ptr() as *mut u8;
+        unsafe {
+            ffi::sancov_counter_increment(base.add(3));
+            // The top four bits select one of 16 counters.
+            ffi::sancov_hash_increment(base, 16, 3 << 60 | 35);
+            ffi::sancov_hash_increment(base, 0, 35);
+        }
+        assert_eq!(counters[3].get(), 2);
+        assert_eq!(counters.count_nonzero(), 1);
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    #[cfg(feature = "ffi")]
+    fn ffi_increment_indices_ignores_out_of_bounds() {
+        let counters = Counters::<16>::new();
+        let base = counters.as_array().as_ptr() as *mut u8;
+        let indices = [1, 5, 5, 16, u32::MAX];
+        unsafe {
+            ffi::sancov_increment_indices(base, 16, indices.as_ptr(), indices.len());
+            ffi::sancov_increment_indices(base, 16, core::ptr::null(), 0);
+        }
+        assert_eq!(counters[1].get(), 1);
+        assert_eq!(counters[5].get(), 2);
+        assert_eq!(counters.count_nonzero(), 2);
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    fn increment_policies_at_overflow() {
+        let counter = Counter(AtomicU8::new(255));
+        counter.increment();
+        assert_eq!(counter.get(), 1);
+
+        let counter = Counter(AtomicU8::new(255));
+        counter.saturating_increment();
+        assert_eq!(counter.get(), 255);
+
+        let counter = Counter(AtomicU8::new(255));
+        counter.wrapping_increment();
+        assert_eq!(counter.get(), 0);
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    #[cfg(feature = "hash_increment")]
+    fn virgin_indices_after_hash_increment() {
+        let counters = Counters::<64>::new();
+        assert!(counters.virgin_indices().eq(0..64));
+
+        counters.hash_increment("wheelies");
+        let i = counters.hash_index("wheelies");
+        assert!(counters.virgin_indices().eq((0..64).filter(|j| *j != i)));
+    }
+
+    #[test]
+    fn increment_table_matches_increment() {
+        for i in 0..=255 {
+            let a = Counter(AtomicU8::new(i));
+            let b = Counter(AtomicU8::new(i));
+            a.increment();
+            b.increment_table();
+            assert_eq!(a.get(), b.get());
+        }
+    }
+
+    #[test]
+    fn increment_by_zero() {
+        let counter = Counter(AtomicU8::new(0));
+        counter.increment_by(0);
+        assert_eq!(counter.get(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "hash_increment")]
+    fn reduce_is_in_range_and_spreads_keys() {
+        assert_eq!(reduce(0, 16), 0);
+        assert_eq!(reduce(u64::MAX, 16), 15);
+        assert_eq!(reduce(u64::MAX, usize::MAX), usize::MAX - 1);
+        assert_eq!(reduce(1 << 63, 16), 8);
+
+        // Sequential integers, integers that differ only in their high bits,
+        // and short strings should all spread across a power-of-two map.
+        let sequential = Counters::<256>::estimate_collisions(0..256u32);
+        let high_bits = Counters::<256>::estimate_collisions((0..256u64).map(|i| i << 56));
+        let strings = Counters::<256>::estimate_collisions((0..=255u8).map(|i| [b'k', b'-', i]));
+        for (used, total) in [sequential, high_bits, strings] {
+            assert_eq!(total, 256);
+            assert!(used > 128, "only {used} of 256 counters used");
+        }
+
+        let counters = Counters::<1000>::new();
+        for key in 0..10_000u64 {
+            assert!(counters.hash_index(&key) < 1000);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn reset_range_out_of_bounds() {
+        let counters = Counters::<16>::new();
+        counters.reset_range(8..17);
+    }
+
+    #[test]
+    fn restore_checkpoint_reproduces_saved_state() {
+        // An odd length exercises both the word-sized chunks and the tail.
+        let counters = Counters::<37>::new();
+        for i in (0..37).step_by(3) {
+            counters[i].increment_by(i as u8 + 1);
+        }
+        let before = counters.snapshot();
+        let mut checkpoint = [0; 37];
+        counters.save_checkpoint(&mut checkpoint);
+        assert_eq!(&checkpoint, before.as_array());
+
+        counters.reset();
+        for i in 0..37 {
+            counters[i].increment_by(200);
+        }
+        counters.restore_checkpoint(&checkpoint);
+        assert_eq!(counters.snapshot(), before);
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    fn add_deltas_into_saturated_counters() {
+        let counters = Counters::<4>::new();
+        counters[0].increment_by(255);
+        counters[1].increment_by(250);
+        counters[2].increment_by(255);
+        counters.add_deltas(&[1, 10, 0, 255]);
+        assert_eq!(counters[0].get(), 1);
+        assert_eq!(counters[1].get(), 5);
+        assert_eq!(counters[2].get(), 255);
+        assert_eq!(counters[3].get(), 255);
+    }
+
+    #[test]
+    #[cfg(feature = "hash_increment")]
+    fn reduce_pow2_matches_multiply_shift() {
+        let hashes = (0..1000u64).map(|i| fxhash::hash64(&i)).chain([
+            0,
+            1,
+            u64::MAX,
+            1 << 63,
+            u64::MAX >> 1,
+        ]);
+        for hash in hashes {
+            for k in 0..usize::BITS {
+                let n = 1usize << k;
+                let expected = ((hash as u128 * n as u128) >> 64) as usize;
+                assert_eq!(reduce_pow2(hash, n), expected, "hash {hash:#x}, n {n}");
+                assert_eq!(reduce(hash, n), expected);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn snapshot_serialize_round_trip() {
+        let counters = Counters::<37>::new();
+        counters[0].increment();
+        counters[36].increment_by(255);
+        let snapshot = counters.snapshot();
+        let bytes = snapshot.serialize();
+        assert_eq!(bytes.len(), 16 + 37);
+        assert_eq!(&bytes[..4], b"SNCV");
+        assert_eq!(Snapshot::<37>::deserialize(&bytes), Ok(snapshot));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn snapshot_deserialize_errors() {
+        let bytes = Counters::<16>::new().snapshot().serialize();
+
+        assert_eq!(
+            Snapshot::<16>::deserialize(&bytes[..15]),
+            Err(SnapshotError::Truncated)
+        );
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] ^= 1;
+        assert_eq!(
+            Snapshot::<16>::deserialize(&bad_magic),
+            Err(SnapshotError::BadMagic)
+        );
+
+        let mut bad_version = bytes.clone();
+        bad_version[4..8].copy_from_slice(&2u32.to_le_bytes());
+        assert_eq!(
+            Snapshot::<16>::deserialize(&bad_version),
+            Err(SnapshotError::UnsupportedVersion(2))
+        );
+
+        assert_eq!(
+            Snapshot::<8>::deserialize(&bytes),
+            Err(SnapshotError::LenMismatch(LenMismatch {
+                expected: 8,
+                found: 16
+            }))
+        );
+
+        assert_eq!(
+            Snapshot::<16>::deserialize(&bytes[..20]),
+            Err(SnapshotError::LenMismatch(LenMismatch {
+                expected: 16,
+                found: 4
+            }))
+        );
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    #[cfg(feature = "hash_increment")]
+    fn increment_type_uses_type_id_slot() {
+        use core::any::TypeId;
+        let counters = Counters::<64>::new();
+        counters.increment_type::<u32>();
+        counters.increment_type::<u32>();
+        counters.increment_type::<str>();
+        let u = counters.hash_index(&TypeId::of::<u32>());
+        let s = counters.hash_index(&TypeId::of::<str>());
+        if u != s {
+            assert_eq!(counters[u].get(), 2);
+            assert_eq!(counters[s].get(), 1);
+        } else {
+            assert_eq!(counters[u].get(), 3);
+        }
+        assert_eq!(counters.count_nonzero(), if u == s { 1 } else { 2 });
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    fn reader_sees_increments() {
+        let counters = Counters::<5>::new();
+        let reader = counters.reader();
+        counters[1].increment();
+        counters[4].increment_by(3);
+        assert_eq!(reader.count_nonzero(), 2);
+        assert_eq!(reader.get(4), Some(3));
+        assert_eq!(reader.get(5), None);
+        assert!(reader.iter().eq([0, 1, 0, 0, 3]));
+        assert_eq!(reader.snapshot(), counters.snapshot());
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    fn neverzero_next_never_returns_zero() {
+        for i in 0..=255u8 {
+            let next = neverzero_next(i);
+            assert_ne!(next, 0);
+            assert_eq!(next, NEVER_ZERO_NEXT[i as usize]);
+            let counter = Counter(AtomicU8::new(i));
+            counter.increment();
+            assert_eq!(counter.get(), next);
+        }
+    }
+
+    #[test]
+    fn from_raw_parts_checks_len() {
+        let err = unsafe { Counters::<8>::from_raw_parts(core::ptr::null_mut(), 7) };
+        assert_eq!(
+            err.err(),
+            Some(LenMismatch {
+                expected: 8,
+                found: 7
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    fn rarity_score_weights_rare_edges() {
+        const ONE: u64 = 1 << 32;
+        let counters = Counters::<19>::new();
+        let mut global_hits = [0u64; 19];
+        for (i, hits) in global_hits.iter_mut().enumerate() {
+            *hits = 1 << i;
+        }
+        assert_eq!(counters.rarity_score(&global_hits), 0);
+
+        counters[0].increment();
+        counters[1].increment_by(200);
+        counters[18].increment();
+        assert_eq!(
+            counters.rarity_score(&global_hits),
+            ONE + ONE / 2 + ONE / (1 << 18)
+        );
+
+        // Never-seen edges count as hit once.
+        global_hits[18] = 0;
+        assert_eq!(counters.rarity_score(&global_hits), ONE + ONE / 2 + ONE);
+
+        counters.reset();
+        counters[5].increment();
+        let rare = counters.rarity_score(&global_hits);
+        counters.reset();
+        counters[10].increment();
+        assert!(rare > counters.rarity_score(&global_hits));
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    fn rarity_score_extremes() {
+        let counters = Counters::<8>::new();
+        for c in counters.iter() {
+            c.increment();
+        }
+        let score = counters.rarity_score(&[0; 8]);
+        assert_eq!(score, 8 << 32);
+        assert_eq!(Counters::<8>::new().rarity_score(&[u64::MAX; 8]), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn seeded_out_of_bounds() {
+        let _ = Counters::<4>::seeded([(0, 1), (4, 1)]);
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    #[cfg(all(
+        feature = "contention_stats",
+        feature = "std",
+        target_has_atomic = "64"
+    ))]
+    fn contention_stats_increments_are_not_lost() {
+        static COUNTERS: Counters<1> = Counters::new();
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..50 {
+                        COUNTERS[0].increment();
+                    }
+                });
+            }
+        });
+        assert_eq!(COUNTERS[0].get(), 200);
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    fn changed_bits_partial_last_word() {
+        let counters = Counters::<130>::new();
+        let mut baseline = [0; 130];
+        baseline[5] = 1;
+        baseline[64] = 7;
+        counters[64].increment_by(7);
+        counters[65].increment();
+        counters[127].increment();
+        counters[128].increment();
+        counters[129].increment_by(2);
+        baseline[129] = 3;
+
+        let mut out = [u64::MAX; 4];
+        counters.changed_bits(&baseline, &mut out);
+        assert_eq!(out, [1 << 5, 1 << 1 | 1 << 63, 0b11, u64::MAX]);
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    fn changed_bits_small_n() {
+        let counters = Counters::<3>::new();
+        counters[2].increment();
+        let mut out = [u64::MAX];
+        counters.changed_bits(&[0, 0, 1], &mut out);
+        assert_eq!(out, [0]);
+        counters.changed_bits(&[1, 0, 0], &mut out);
+        assert_eq!(out, [0b101]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn changed_bits_out_too_short() {
+        let counters = Counters::<65>::new();
+        counters.changed_bits(&[0; 65], &mut [0; 1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn trace_cmp_pc_rejects_bad_width() {
+        trace_cmp_pc(0, 1, 2, 3);
+    }
+
+    /// A comparison that reached `__sanitizer_weak_hook_memcmp`, as
+    /// `(s1, s2, result)`.
+    #[cfg(all(feature = "std", target_os = "linux"))]
+    type TracedMemcmp = (alloc::vec::Vec<u8>, alloc::vec::Vec<u8>, i32);
+
+    /// The comparisons that reached the hook from `TRACE_CMP_PC_SITE`.
+    #[cfg(all(feature = "std", target_os = "linux"))]
+    static TRACED_MEMCMPS: std::sync::Mutex<alloc::vec::Vec<TracedMemcmp>> =
+        std::sync::Mutex::new(alloc::vec::Vec::new());
+
+    #[cfg(all(feature = "std", target_os = "linux"))]
+    const TRACE_CMP_PC_SITE: usize = 0x5a4c_0c0d;
+
+    #[cfg(all(feature = "std", target_os = "linux"))]
+    #[no_mangle]
+    unsafe extern "C" fn __sanitizer_weak_hook_memcmp(
+        caller_pc: *const core::ffi::c_void,
+        s1: *const core::ffi::c_void,
+        s2: *const core::ffi::c_void,
+        n: usize,
+        result: core::ffi::c_int,
+    ) {
+        if caller_pc as usize == TRACE_CMP_PC_SITE {
+            let s1 = core::slice::from_raw_parts(s1.cast::<u8>(), n).to_vec();
+            let s2 = core::slice::from_raw_parts(s2.cast::<u8>(), n).to_vec();
+            TRACED_MEMCMPS.lock().unwrap().push((s1, s2, result));
+        }
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    #[cfg(all(feature = "std", target_os = "linux"))]
+    fn trace_cmp_pc_reports_native_endian_bytes() {
+        let sign = |a: &[u8], b: &[u8]| a.cmp(b) as i32;
+
+        trace_cmp_pc(TRACE_CMP_PC_SITE, 0xffff_0102, 0x0201, 2);
+        trace_cmp_pc(TRACE_CMP_PC_SITE, 0xdead_beef, 0xdead_beef, 4);
+        trace_cmp_pc(TRACE_CMP_PC_SITE, 1, u64::MAX, 8);
+
+        let (a2, b2) = (0x0102u16.to_ne_bytes(), 0x0201u16.to_ne_bytes());
+        let (a4, b4) = (0xdead_beefu32.to_ne_bytes(), 0xdead_beefu32.to_ne_bytes());
+        let (a8, b8) = (1u64.to_ne_bytes(), u64::MAX.to_ne_bytes());
+        assert_eq!(
+            *TRACED_MEMCMPS.lock().unwrap(),
+            [
+                (a2.to_vec(), b2.to_vec(), sign(&a2, &b2)),
+                (a4.to_vec(), b4.to_vec(), 0),
+                (a8.to_vec(), b8.to_vec(), -1),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    #[cfg(feature = "alloc")]
+    fn sparse_round_trip() {
+        let counters = Counters::<20000>::new();
+        assert!(counters.snapshot().encode_sparse().is_empty());
+        for i in [0, 1, 127, 128, 16383, 16384, 19999] {
+            counters[i].increment_by((i % 251) as u8 + 1);
+        }
+        let snapshot = counters.snapshot();
+        let bytes = snapshot.encode_sparse();
+        assert_eq!(bytes.len(), 2 + 2 + 2 + 3 + 3 + 4 + 4);
+        assert_eq!(Snapshot::<20000>::decode_sparse(&bytes), Ok(snapshot));
+        assert_eq!(
+            Snapshot::<20000>::decode_sparse(&[]),
+            Ok(Counters::<20000>::new().snapshot())
+        );
+    }
+
+    #[test]
+    fn sparse_decode_errors() {
+        type S = Snapshot<300>;
+        assert_eq!(S::decode_sparse(&[0x80]), Err(SparseError::Truncated));
+        assert_eq!(S::decode_sparse(&[3]), Err(SparseError::Truncated));
+        assert_eq!(
+            S::decode_sparse(&[0xac, 0x02, 1]),
+            Err(SparseError::IndexOutOfBounds(300))
+        );
+        assert_eq!(
+            S::decode_sparse(&[5, 1, 4, 1]),
+            Err(SparseError::IndexNotAscending(4))
+        );
+        assert_eq!(
+            S::decode_sparse(&[5, 1, 5, 1]),
+            Err(SparseError::IndexNotAscending(5))
+        );
+        let mut overlong = [0xff; 11];
+        overlong[10] = 0x01;
+        assert_eq!(S::decode_sparse(&overlong), Err(SparseError::BadVarint));
+        let mut too_big = [0xff; 10];
+        too_big[9] = 0x02;
+        assert_eq!(S::decode_sparse(&too_big), Err(SparseError::BadVarint));
+        let mut max = [0xff; 11];
+        max[9] = 0x01;
+        assert_eq!(
+            S::decode_sparse(&max),
+            Err(SparseError::IndexOutOfBounds(u64::MAX))
+        );
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    fn mark_leaves_counter_at_one() {
+        let counter = Counter(AtomicU8::new(0));
+        for _ in 0..1000 {
+            counter.mark();
+            assert_eq!(counter.get(), 1);
+        }
+        counter.increment_by(4);
+        counter.mark();
+        assert_eq!(counter.get(), 5);
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    fn update_virgin_counts_new_buckets() {
+        let counters = Counters::<19>::new();
+        let mut virgin = [0xFF; 19];
+        assert_eq!(counters.update_virgin(&mut virgin), 0);
+
+        counters[0].increment_by(5);
+        counters[9].increment();
+        counters[18].increment_by(200);
+        assert_eq!(counters.update_virgin(&mut virgin), 3);
+        assert_eq!(virgin[0], !8);
+        assert_eq!(virgin[18], !128);
+        assert_eq!(counters.update_virgin(&mut virgin), 0);
+
+        // 7 is in the same bucket as 5, but 8 is not.
+        counters[0].increment_by(2);
+        assert_eq!(counters.update_virgin(&mut virgin), 0);
+        counters[0].increment();
+        counters[9].increment();
+        assert_eq!(counters.update_virgin(&mut virgin), 2);
+
+        // Falling back to a lower bucket that was already seen is not new.
+        counters.reset();
+        counters[9].increment();
+        assert_eq!(counters.update_virgin(&mut virgin), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "ffi")]
+    fn ffi_header_matches_exports() {
+        // If any of these change, update `include/sancov.h` to match.
+        let _: unsafe extern "C" fn(*mut u8) = ffi::sancov_counter_increment;
+        let _: unsafe extern "C" fn(*mut u8, usize, u64) = ffi::sancov_hash_increment;
+        let _: unsafe extern "C" fn(*mut u8, usize, *const u32, usize) =
+            ffi::sancov_increment_indices;
+        let declared = [
+            "void sancov_counter_increment(uint8_t *ptr);",
+            "void sancov_hash_increment(uint8_t *base, size_t len, uint64_t hash);",
+            "void sancov_increment_indices(uint8_t *base, size_t len, const uint32_t *indices, size_t count);",
+        ];
+
+        let header = include_str!("../include/sancov.h");
+        assert!(header
+            .lines()
+            .filter(|l| l.contains("sancov_") && l.ends_with(';'))
+            .eq(declared));
+
+        let exported = include_str!("ffi.rs")
+            .matches("extern \"C\" fn sancov_")
+            .count();
+        assert_eq!(exported, declared.len());
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    #[cfg(feature = "hash_increment")]
+    fn hash_increment_raw_is_deterministic() {
+        let counters = Counters::<37>::new();
+        for hash in [0, 1, u64::MAX, 0x9e37_79b9_7f4a_7c15] {
+            counters.reset();
+            counters.hash_increment_raw(hash);
+            counters.hash_increment_raw(hash);
+            assert_eq!(counters.count_nonzero(), 1);
+            assert_eq!(counters[reduce(hash, 37)].get(), 2);
+        }
+
+        counters.reset();
+        counters.hash_increment("wheelies");
+        counters.hash_increment_raw(fxhash::hash64("wheelies"));
+        assert_eq!(counters[counters.hash_index("wheelies")].get(), 2);
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    #[cfg(all(feature = "ffi", feature = "hash_increment"))]
+    fn ffi_hash_increment_matches_hash_increment_raw() {
+        let counters = Counters::<37>::new();
+        let base = counters.as_array().as_ptr() as *mut u8;
+        for hash in [0, 1, 12345, u64::MAX, 0x9e37_79b9_7f4a_7c15] {
+            counters.reset();
+            unsafe { ffi::sancov_hash_increment(base, 37, hash) };
+            counters.hash_increment_raw(hash);
+            assert_eq!(counters[reduce(hash, 37)].get(), 2);
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "hash_increment", feature = "alloc"))]
+    fn estimate_collisions_large_n() {
+        let keys = || (0..5000u32).map(|i| i.wrapping_mul(0x9e37_79b9));
+        let (used, total) = Counters::<{ 1 << 20 }>::estimate_collisions(keys());
+        assert_eq!(total, 5000);
+        let expected = keys()
+            .map(|k| reduce(fxhash::hash64(&k), 1 << 20))
+            .collect::<alloc::collections::BTreeSet<_>>()
+            .len();
+        assert_eq!(used, expected);
+    }
+
+    #[test]
+    #[cfg(not(sancov_disabled))]
+    #[cfg(feature = "std")]
+    fn reset_all_skips_cells() {
+        let cell: &'static CountersCell<8> = alloc::boxed::Box::leak(Default::default());
+        cell[2].increment();
+        cell.register();
+        let start = cell.as_array().as_ptr() as *const u8;
+        assert!(registered_regions().contains(&(start, 8)));
+        reset_all();
+        assert_eq!(cell[2].get(), 1);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::{Counters, NoConsumer};
+
+impl<const N: usize> Counters<N> {
+    /// Register the given counters with libFuzzer, at most once.
+    ///
+    /// Requires the `libfuzzer` feature, which implies `register_once`, so
+    /// this can be called from every place that might need the counters
+    /// without ever registering them twice. That matters for libFuzzer: it
+    /// only ignores a repeated registration when it immediately follows the
+    /// original one, and otherwise treats the same counters as a second module,
+    /// and counts their coverage twice.
+    ///
+    /// Ordinary `static` counters are separate from the region that the
+    /// compiler's own instrumentation registers in a `cargo fuzz` build, so the
+    /// two coexist, and libFuzzer collects features from both after each run.
+    /// Counters declared with `inline_counters!`, however, are already part of
+    /// that region, and must not be registered again.
+    ///
+    /// Returns [`NoConsumer`][crate::NoConsumer] if libFuzzer, or any other
+    /// consumer, is not linked in, e.g. when the fuzz target is built into a
+    /// plain binary for reproducing crashes.
+    ///
+    /// # Ordering
+    ///
+    /// libFuzzer reports the number of registered counters, and sizes some of
+    /// its internal state, when it starts up, before it runs any input, so the
+    /// counters must be registered before then. Either register them from a
+    /// static constructor, which runs before `main` and therefore before
+    /// libFuzzer's driver, with the `counters!` macro, or from
+    /// `LLVMFuzzerInitialize`, which libFuzzer calls before it runs the first
+    /// input. With `libfuzzer-sys`, that is the `init` block of
+    /// `fuzz_target!`:
+    ///
+    /// ```ignore
+    /// #![no_main]
+    ///
+    /// use libfuzzer_sys::fuzz_target;
+    /// use sancov::Counters;
+    ///
+    /// static INTERPRETER: Counters<4096> = Counters::new();
+    ///
+    /// fuzz_target!(
+    ///     init: {
+    ///         INTERPRETER.register_for_libfuzzer().unwrap();
+    ///     },
+    ///     |data: &[u8]| {
+    ///         my_interpreter::run(data, &INTERPRETER);
+    ///     }
+    /// );
+    /// ```
+    ///
+    /// Registering from inside the fuzz target itself, i.e. from
+    /// `LLVMFuzzerTestOneInput`, is too late: coverage from the first input is
+    /// lost, and libFuzzer's startup statistics omit the counters.
+    pub fn register_for_libfuzzer(&'static self) -> Result<(), NoConsumer> {
+        self.try_register()
+    }
+}
@@ -0,0 +1,119 @@
+use crate::Counters;
+use core::cell::UnsafeCell;
+use core::sync::atomic::Ordering;
+
+/// A private, non-atomic bank of `N` counters for use by a single thread.
+///
+/// In multi-threaded programs, incrementing a shared
+/// [`Counters<N>`][crate::Counters] from many threads causes cache-line
+/// contention. Instead, each thread can increment its own
+/// `ThreadLocalCounters<N>` without any atomic operations, and periodically
+/// [fold][crate::ThreadLocalCounters::fold_into] it into the shared counters.
+///
+/// Thread-local counters are never registered with the `SanitizerCoverage`
+/// consumer; they are private scratch space. Only the shared `Counters<N>`
+/// that they are folded into should be registered, and the consumer will not
+/// observe any increments until they are folded.
+///
+/// `ThreadLocalCounters<N>` is not `Sync`.
+///
+/// # Example
+///
+/// ```
+/// use sancov::{Counters, ThreadLocalCounters};
+///
+/// static COUNTERS: Counters<4096> = Counters::new();
+///
+/// thread_local! {
+///     static LOCAL: ThreadLocalCounters<4096> = const { ThreadLocalCounters::new() };
+/// }
+///
+/// COUNTERS.register();
+///
+/// let threads: Vec<_> = (0..4)
+///     .map(|_| {
+///         std::thread::spawn(|| {
+///             LOCAL.with(|local| {
+///                 for _ in 0..10 {
+///                     local.increment(42);
+///                 }
+///                 local.fold_into(&COUNTERS);
+///             })
+///         })
+///     })
+///     .collect();
+/// for t in threads {
+///     t.join().unwrap();
+/// }
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+/// ```
+pub struct ThreadLocalCounters<const N: usize>(UnsafeCell<[u8; N]>);
+
+impl<const N: usize> ThreadLocalCounters<N> {
+    /// Construct a new bank of `N` thread-local counters.
+    ///
+    /// # Compile-time errors
+    ///
+    /// `N` must not be zero.
+    pub const fn new() -> Self {
+        const { assert!(N != 0, "`N` must not be zero") };
+        ThreadLocalCounters(UnsafeCell::new([0; N]))
+    }
+
+    #[inline]
+    #[allow(clippy::mut_from_ref)]
+    fn bytes(&self) -> &mut [u8; N] {
+        // Safety: we are not `Sync` and never hand out references to the
+        // underlying bytes, so this is the only live reference.
+        unsafe { &mut *self.0.get() }
+    }
+
+    /// Increment the counter at `index`, saturating at `u8::MAX`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= N`.
+    #[inline]
+    pub fn increment(&self, index: usize) {
+        let count = &mut self.bytes()[index];
+        *count = count.saturating_add(1);
+    }
+
+    /// Get the value of the counter at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= N`.
+    #[inline]
+    pub fn get(&self, index: usize) -> u8 {
+        self.bytes()[index]
+    }
+
+    /// Fold these counters into the shared `global` counters, and reset these
+    /// counters back to zero.
+    ///
+    /// Each non-zero local counter is added to its corresponding global
+    /// counter, saturating at `u8::MAX`.
+    ///
+    /// The global counters are updated with relaxed loads and stores, so
+    /// concurrent folds from other threads, or concurrent increments, may be
+    /// lost. This is acceptable for coverage feedback.
+    pub fn fold_into(&self, global: &Counters<N>) {
+        for (local, global) in self.bytes().iter_mut().zip(global.atomics()) {
+            if *local != 0 {
+                let count = global.load(Ordering::Relaxed);
+                global.store(count.saturating_add(*local), Ordering::Relaxed);
+                *local = 0;
+            }
+        }
+    }
+}
+
+impl<const N: usize> Default for ThreadLocalCounters<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,250 @@
+/// Declare a static [`Counters<N>`][crate::Counters] that is automatically
+/// registered with the `SanitizerCoverage` consumer at startup, before `main`
+/// runs.
+///
+/// Registration is performed by a static constructor placed in the platform's
+/// initializer section (`.init_array` on ELF targets, `__mod_init_func` on
+/// Mach-O targets, and `.CRT$XCU` on Windows), which is the same mechanism that
+/// [the `ctor` crate](https://crates.io/crates/ctor) uses.
+///
+/// # Compile-time errors
+///
+/// On targets without one of those initializer sections, such as bare-metal
+/// targets, the counters could never be registered, so using this macro is a
+/// compile-time error. Declare a `static` [`Counters<N>`][crate::Counters]
+/// and call [`register`][crate::Counters::register] manually instead.
+///
+/// # Example
+///
+/// ```
+/// sancov::counters!(MY_COUNTERS: 4096);
+///
+/// fn main() {
+///     // No need to call `MY_COUNTERS.register()`!
+///     MY_COUNTERS[42].increment();
+/// }
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+/// ```
+///
+/// Attributes and a visibility can be given as well:
+///
+/// ```
+/// sancov::counters! {
+///     /// Counters for the parser.
+///     pub PARSER_COUNTERS: 1024
+/// }
+/// # fn main() {}
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+/// ```
+#[macro_export]
+macro_rules! counters {
+    ( $( #[$attr:meta] )* $vis:vis $name:ident : $n:expr $(;)? ) => {
+        $( #[$attr] )*
+        $vis static $name: $crate::Counters<{ $n }> = $crate::Counters::new();
+
+        const _: () = {
+            #[used]
+            #[cfg_attr(
+                any(
+                    target_os = "linux",
+                    target_os = "android",
+                    target_os = "freebsd",
+                    target_os = "netbsd",
+                    target_os = "openbsd",
+                    target_os = "dragonfly",
+                    target_os = "illumos",
+                    target_os = "solaris",
+                    target_os = "haiku",
+                    target_os = "fuchsia",
+                ),
+                link_section = ".init_array"
+            )]
+            #[cfg_attr(
+                any(target_os = "macos", target_os = "ios", target_os = "tvos", target_os = "watchos"),
+                link_section = "__DATA,__mod_init_func"
+            )]
+            #[cfg_attr(windows, link_section = ".CRT$XCU")]
+            static REGISTER: extern "C" fn() = {
+                extern "C" fn register() {
+                    $name.register();
+                }
+                register
+            };
+
+            #[cfg(not(any(
+                target_os = "linux",
+                target_os = "android",
+                target_os = "freebsd",
+                target_os = "netbsd",
+                target_os = "openbsd",
+                target_os = "dragonfly",
+                target_os = "illumos",
+                target_os = "solaris",
+                target_os = "haiku",
+                target_os = "fuchsia",
+                target_os = "macos",
+                target_os = "ios",
+                target_os = "tvos",
+                target_os = "watchos",
+                windows,
+            )))]
+            compile_error!(
+                "`counters!` cannot register counters automatically on this target; \
+                 declare a `static` `Counters<N>` and call `register` manually"
+            );
+        };
+    };
+}
+
+/// Declare a static [`Counters<N>`][crate::Counters] in the named link
+/// section.
+///
+/// This is for kernels and bare-metal targets, whose linker scripts may need
+/// large counter arrays placed somewhere other than the default `.bss`.
+/// Registration is unaffected, since it only uses the counters' address at
+/// runtime: call [`register`][crate::Counters::register] as usual.
+///
+/// The section must be writable. On ELF targets, LLVM emits zero-initialized
+/// data in a custom section as `PROGBITS`, taking up space in the file,
+/// unless the section's name starts with `.bss.`, in which case it is emitted
+/// as `NOBITS`.
+///
+/// # Example
+///
+/// ```
+/// sancov::counters_in_section!(MAP: 4096, ".bss.coverage");
+/// sancov::counters_in_section! {
+///     /// Counters for the scheduler.
+///     pub SCHED: 1024, ".bss.coverage"
+/// }
+///
+/// fn main() {
+///     MAP.register();
+///     MAP[42].increment();
+/// }
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+/// ```
+#[macro_export]
+macro_rules! counters_in_section {
+    ( $( #[$attr:meta] )* $vis:vis $name:ident : $n:expr, $section:literal $(;)? ) => {
+        $( #[$attr] )*
+        #[link_section = $section]
+        $vis static $name: $crate::Counters<{ $n }> = $crate::Counters::new();
+    };
+}
+
+/// Declare a static [`Counters<N>`][crate::Counters] in the `__sancov_cntrs`
+/// section, where `-fsanitize-coverage=inline-8bit-counters` places its
+/// counters.
+///
+/// Counters declared this way are not registered individually. In a binary
+/// compiled with `inline-8bit-counters` instrumentation, the compiler's module
+/// constructor registers the whole section, including these counters. In a
+/// binary without that instrumentation, call
+/// [`register_inline`][crate::register_inline] once at startup instead. See
+/// its documentation for the linker requirements.
+///
+/// # Example
+///
+/// ```
+/// sancov::inline_counters!(PARSER: 1024);
+/// sancov::inline_counters! {
+///     /// Counters for the evaluator.
+///     pub EVAL: 4096
+/// }
+///
+/// fn main() {
+///     # #[cfg(target_os = "linux")]
+///     assert!(sancov::register_inline());
+///     PARSER[42].increment();
+/// }
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(start: *const u8, end: *const u8) {
+/// #     assert_eq!(end as usize - start as usize, 1024 + 4096);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! inline_counters {
+    ( $( #[$attr:meta] )* $vis:vis $name:ident : $n:expr $(;)? ) => {
+        $( #[$attr] )*
+        #[used]
+        #[cfg_attr(
+            any(target_os = "macos", target_os = "ios", target_os = "tvos", target_os = "watchos"),
+            link_section = "__DATA,__sancov_cntrs"
+        )]
+        #[cfg_attr(windows, link_section = ".SCOV$CM")]
+        #[cfg_attr(
+            not(any(
+                windows,
+                target_os = "macos",
+                target_os = "ios",
+                target_os = "tvos",
+                target_os = "watchos",
+            )),
+            link_section = "__sancov_cntrs"
+        )]
+        $vis static $name: $crate::Counters<{ $n }> = $crate::Counters::new();
+    };
+}
+
+/// Declare a fieldless `enum` that implements
+/// [`CoverageIndex`][crate::CoverageIndex], mapping each variant to its
+/// position in the declaration.
+///
+/// Explicit discriminants are not supported, so that the indices are always
+/// dense. Use the result with [`VariantCounters`][crate::VariantCounters],
+/// which rejects enums with more variants than it has counters.
+///
+/// # Example
+///
+/// ```
+/// use sancov::{CoverageIndex, VariantCounters};
+///
+/// sancov::coverage_index! {
+///     #[derive(Clone, Copy, Debug)]
+///     pub enum State {
+///         Idle,
+///         Running,
+///         Done,
+///     }
+/// }
+///
+/// assert_eq!(State::COUNT, 3);
+/// assert_eq!(State::Done.index(), 2);
+///
+/// static STATES: VariantCounters<State, { State::COUNT }> = VariantCounters::new();
+/// STATES.increment(&State::Running);
+/// assert_eq!(STATES.get(&State::Running), 1);
+/// ```
+#[macro_export]
+macro_rules! coverage_index {
+    (
+        $( #[$attr:meta] )*
+        $vis:vis enum $name:ident {
+            $( $( #[$variant_attr:meta] )* $variant:ident ),* $(,)?
+        }
+    ) => {
+        $( #[$attr] )*
+        $vis enum $name {
+            $( $( #[$variant_attr] )* $variant, )*
+        }
+
+        impl $crate::CoverageIndex for $name {
+            const COUNT: usize = [$( stringify!($variant) ),*].len();
+
+            #[inline]
+            fn index(&self) -> usize {
+                match self {
+                    $( $name::$variant => $name::$variant as usize, )*
+                }
+            }
+        }
+    };
+}
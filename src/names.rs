@@ -0,0 +1,110 @@
+use crate::Counters;
+use core::ops::Range;
+
+/// Human-readable names for `N` counters.
+///
+/// `Names<N>` is a companion to a [`Counters<N>`][crate::Counters] that
+/// attaches a `&'static str` label, such as `module::function`, to each counter
+/// or range of counters, so that diagnostics and reports can print names
+/// instead of bare indices. Counters that have not been named have the empty
+/// name, `""`.
+///
+/// Names can be built in a `const` context, so they can live in a `static`
+/// next to the counters they describe.
+///
+/// # Example
+///
+/// ```
+/// use sancov::{Counters, Names};
+///
+/// static COUNTERS: Counters<8> = Counters::new();
+/// static NAMES: Names<8> = Names::new()
+///     .with_range(0..4, "parser::parse_expr")
+///     .with_range(4..8, "eval::eval_expr");
+///
+/// COUNTERS.register();
+/// COUNTERS[5].increment();
+///
+/// let hit: Vec<_> = COUNTERS
+///     .iter()
+///     .enumerate()
+///     .filter(|(_, c)| c.get() != 0)
+///     .map(|(i, _)| COUNTERS.name(&NAMES, i))
+///     .collect();
+/// assert_eq!(hit, ["eval::eval_expr"]);
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Names<const N: usize>([&'static str; N]);
+
+impl<const N: usize> Names<N> {
+    /// Construct a new set of `N` names, all empty.
+    pub const fn new() -> Self {
+        Names([""; N])
+    }
+
+    /// Construct a set of names from an array with one name per counter.
+    pub const fn from_array(names: [&'static str; N]) -> Self {
+        Names(names)
+    }
+
+    /// Name every counter in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds. In a `const` context, such as the
+    /// initializer of a `static`, that is a compile-time error.
+    pub const fn with_range(mut self, range: Range<usize>, name: &'static str) -> Self {
+        assert!(
+            range.start <= range.end && range.end <= N,
+            "range out of bounds"
+        );
+        let mut i = range.start;
+        while i < range.end {
+            self.0[i] = name;
+            i += 1;
+        }
+        self
+    }
+
+    /// Get the name of the counter at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= N`.
+    #[inline]
+    pub fn get(&self, index: usize) -> &'static str {
+        self.0[index]
+    }
+
+    /// Get all of the names.
+    #[inline]
+    pub fn as_array(&self) -> &[&'static str; N] {
+        &self.0
+    }
+}
+
+impl<const N: usize> Default for Names<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Counters<N> {
+    /// Get the name of the counter at `index` from `names`.
+    ///
+    /// This is shorthand for `names.get(index)`, that additionally checks that
+    /// `names` describes counters of the same size. See
+    /// [`Names`][crate::Names] for an example.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= N`.
+    #[inline]
+    pub fn name(&self, names: &Names<N>, index: usize) -> &'static str {
+        names.get(index)
+    }
+}
@@ -0,0 +1,180 @@
+use crate::atomic::{AtomicU8, Ordering};
+use crate::Counters;
+use core::cell::UnsafeCell;
+use core::ops::Index;
+
+/// A collection of `N` edges that each fire at most once.
+///
+/// This is the manual equivalent of what a `-fsanitize-coverage=trace-pc-guard`
+/// consumer does when it zeroes a guard on first hit: after an edge has fired,
+/// hitting it again is a single relaxed load that does nothing. That makes
+/// `OnceEdges` a cheap way to record "new function entry" style coverage, where
+/// only whether something happened matters, in code that is too hot for
+/// [`Counters`][crate::Counters].
+///
+/// `OnceEdges<N>` has the same representation as `[u8; N]` and as
+/// `Counters<N>`: an edge that has fired is `1`, and one that has not is `0`.
+/// So the edges can be registered with the `SanitizerCoverage` consumer as
+/// 8-bit counters, next to any hit-count edges.
+///
+/// # Example
+///
+/// ```
+/// use sancov::OnceEdges;
+///
+/// static ENTRIES: OnceEdges<64> = OnceEdges::new();
+/// ENTRIES.register();
+///
+/// fn parse() {
+///     if ENTRIES[3].hit() {
+///         println!("parse entered for the first time");
+///     }
+/// }
+///
+/// parse();
+/// parse();
+/// assert!(ENTRIES[3].is_hit());
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+/// ```
+#[repr(transparent)]
+pub struct OnceEdges<const N: usize>(UnsafeCell<[u8; N]>);
+
+unsafe impl<const N: usize> Send for OnceEdges<N> {}
+unsafe impl<const N: usize> Sync for OnceEdges<N> {}
+
+impl<const N: usize> OnceEdges<N> {
+    /// Construct a new set of `N` edges, none of which have fired.
+    ///
+    /// # Compile-time errors
+    ///
+    /// `N` must not be zero.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        const { assert!(N != 0, "`N` must not be zero") };
+        OnceEdges(UnsafeCell::new([0; N]))
+    }
+
+    /// Get the underlying array of edges.
+    #[inline]
+    pub fn as_array(&self) -> &[OnceEdge; N] {
+        unsafe {
+            let ptr: *mut [u8; N] = self.0.get();
+            let ptr: *const [OnceEdge; N] = ptr as _;
+            &*ptr
+        }
+    }
+
+    /// View these edges as 8-bit counters.
+    ///
+    /// Incrementing the returned counters directly leaves edges with values
+    /// other than `0` and `1`, which [`OnceEdge`][crate::OnceEdge] treats as
+    /// having fired.
+    #[inline]
+    pub fn as_counters(&self) -> &Counters<N> {
+        unsafe { &*(self as *const Self as *const Counters<N>) }
+    }
+
+    /// Register these edges with the `SanitizerCoverage` consumer, as 8-bit
+    /// counters.
+    ///
+    /// See [`Counters::register`][crate::Counters::register].
+    #[inline]
+    pub fn register(&'static self) {
+        self.as_counters().register();
+    }
+
+    /// Count how many edges have fired.
+    #[inline]
+    pub fn count_hit(&self) -> usize {
+        self.as_counters().count_nonzero()
+    }
+
+    /// Reset every edge, so that each can fire once more.
+    #[inline]
+    pub fn reset(&self) {
+        self.as_counters().reset();
+    }
+}
+
+impl<const N: usize> Index<usize> for OnceEdges<N> {
+    type Output = OnceEdge;
+
+    #[inline]
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.as_array()[index]
+    }
+}
+
+/// A single edge that fires at most once.
+///
+/// It has the same representation as a `u8`.
+#[repr(transparent)]
+pub struct OnceEdge(AtomicU8);
+
+impl OnceEdge {
+    /// Construct a new edge that has not fired.
+    #[inline]
+    pub const fn new() -> Self {
+        OnceEdge(AtomicU8::new(0))
+    }
+
+    /// Fire this edge, returning whether this was its first hit.
+    ///
+    /// Once the edge has fired, this is a single relaxed load. Otherwise it is
+    /// a relaxed compare-and-swap, so when several threads race to fire the
+    /// edge, exactly one of them sees `true`.
+    #[inline]
+    pub fn hit(&self) -> bool {
+        if cfg!(sancov_disabled) || self.0.load(Ordering::Relaxed) != 0 {
+            return false;
+        }
+        self.0
+            .compare_exchange(0, 1, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    /// Has this edge fired?
+    #[inline]
+    pub fn is_hit(&self) -> bool {
+        self.0.load(Ordering::Relaxed) != 0
+    }
+
+    /// Reset this edge, so that it can fire once more.
+    #[inline]
+    pub fn reset(&self) {
+        self.0.store(0, Ordering::Relaxed);
+    }
+}
+
+impl Default for OnceEdge {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, not(sancov_disabled)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fire_once() {
+        let edges = OnceEdges::<5>::new();
+        assert!(edges[2].hit());
+        assert!(!edges[2].hit());
+        assert!(edges[2].is_hit());
+        assert!(!edges[3].is_hit());
+        assert_eq!(edges.count_hit(), 1);
+        assert!(edges
+            .as_counters()
+            .iter()
+            .map(|c| c.get())
+            .eq([0, 0, 1, 0, 0]));
+
+        edges.reset();
+        assert!(!edges[2].is_hit());
+        assert!(edges[2].hit());
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{reduce, Counters};
+
+/// How many consecutive slots are probed for a key before one is evicted.
+const PROBE_LEN: usize = 8;
+
+/// An identity-preserving map from logical keys to a set of `N` counters.
+///
+/// [`hash_increment`][crate::Counters::hash_increment] folds an unbounded
+/// number of logical counters onto `N` actual counters, but forgets which
+/// logical key each counter stands for. A `SparseOverlay<K, N>` is a parallel
+/// structure to a [`Counters<N>`][crate::Counters] that remembers, for each
+/// counter, the key that currently owns it, so you can both increment by key
+/// and later ask which keys are live. It is a fixed-size open-addressing table
+/// that never allocates.
+///
+/// A key's home slot is the one `hash_increment` would choose for it, and the
+/// key is placed in the first free slot among the `8` slots starting there,
+/// wrapping around at `N`. If all of those slots are owned by other keys, the
+/// slot with the smallest counter value is evicted, the first such slot on a
+/// tie: its counter is reset to zero and handed over to the new key. So keys
+/// that are hit rarely make way for new keys, and keys that are hit often keep
+/// their counters. Evictions are only possible once more than `N` keys, or
+/// more than `8` keys with nearby home slots, have been seen.
+///
+/// Requires the `hash_increment` feature.
+///
+/// # Example
+///
+/// ```
+/// use sancov::{Counters, SparseOverlay};
+///
+/// static COUNTERS: Counters<64> = Counters::new();
+/// COUNTERS.register();
+///
+/// let mut overlay = SparseOverlay::<&str, 64>::new();
+/// overlay.increment(&COUNTERS, "open");
+/// overlay.increment(&COUNTERS, "read");
+/// overlay.increment(&COUNTERS, "read");
+///
+/// let mut live: Vec<_> = overlay.live(&COUNTERS).collect();
+/// live.sort();
+/// assert_eq!(live, [(&"open", 1), (&"read", 2)]);
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+/// ```
+#[derive(Clone, Debug)]
+pub struct SparseOverlay<K, const N: usize> {
+    keys: [Option<K>; N],
+}
+
+impl<K, const N: usize> SparseOverlay<K, N>
+where
+    K: core::hash::Hash + Eq,
+{
+    /// Construct a new overlay in which no counters are owned by any key.
+    ///
+    /// # Compile-time errors
+    ///
+    /// `N` must not be zero.
+    pub const fn new() -> Self {
+        const { assert!(N != 0, "`N` must not be zero") };
+        SparseOverlay {
+            keys: [const { None }; N],
+        }
+    }
+
+    /// Increment the counter in `counters` that is owned by `key`, assigning
+    /// one to it first if necessary, and return that counter's index.
+    ///
+    /// This may evict another key; see [`SparseOverlay`][crate::SparseOverlay]
+    /// for the policy.
+    pub fn increment(&mut self, counters: &Counters<N>, key: K) -> usize {
+        let home = reduce(fxhash::hash64(&key), N);
+        let mut victim = home;
+        let mut victim_count = u8::MAX;
+        for i in probe::<N>(home) {
+            match &self.keys[i] {
+                Some(k) if *k == key => {
+                    counters[i].increment();
+                    return i;
+                }
+                Some(_) => {
+                    let count = counters[i].get();
+                    if count < victim_count {
+                        victim = i;
+                        victim_count = count;
+                    }
+                }
+                None => {
+                    self.keys[i] = Some(key);
+                    counters[i].increment();
+                    return i;
+                }
+            }
+        }
+        self.keys[victim] = Some(key);
+        counters[victim].reset();
+        counters[victim].increment();
+        victim
+    }
+
+    /// Get the index of the counter owned by `key`, if any.
+    pub fn slot(&self, key: &K) -> Option<usize> {
+        let home = reduce(fxhash::hash64(key), N);
+        probe::<N>(home).find(|&i| self.keys[i].as_ref() == Some(key))
+    }
+
+    /// Get the key that owns the counter at `index`, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= N`.
+    #[inline]
+    pub fn key(&self, index: usize) -> Option<&K> {
+        self.keys[index].as_ref()
+    }
+
+    /// Iterate over the keys whose counters in `counters` are non-zero, along
+    /// with those counters' current values, in index order.
+    pub fn live<'a>(&'a self, counters: &'a Counters<N>) -> impl Iterator<Item = (&'a K, u8)> {
+        self.keys
+            .iter()
+            .zip(counters.iter())
+            .filter_map(|(k, c)| match (k, c.get()) {
+                (Some(k), v) if v != 0 => Some((k, v)),
+                _ => None,
+            })
+    }
+
+    /// Release every counter from its key.
+    ///
+    /// This does not reset the counters themselves.
+    pub fn clear(&mut self) {
+        self.keys = [const { None }; N];
+    }
+}
+
+impl<K, const N: usize> Default for SparseOverlay<K, N>
+where
+    K: core::hash::Hash + Eq,
+{
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The indices probed for a key whose home slot is `home`.
+#[inline]
+fn probe<const N: usize>(home: usize) -> impl Iterator<Item = usize> {
+    (0..PROBE_LEN.min(N)).map(move |i| (home + i) % N)
+}
+
+#[cfg(all(test, not(sancov_disabled)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_keys() {
+        let counters = Counters::<16>::new();
+        let mut overlay = SparseOverlay::<u32, 16>::new();
+        let i = overlay.increment(&counters, 7);
+        assert_eq!(overlay.increment(&counters, 7), i);
+        assert_eq!(overlay.slot(&7), Some(i));
+        assert_eq!(overlay.key(i), Some(&7));
+        assert_eq!(counters[i].get(), 2);
+        assert_eq!(overlay.slot(&8), None);
+
+        // Sixteen keys fit without any evictions.
+        for key in 100..115 {
+            overlay.increment(&counters, key);
+        }
+        for key in (100..115).chain([7]) {
+            assert!(overlay.slot(&key).is_some(), "{key} was evicted");
+        }
+        assert_eq!(overlay.live(&counters).count(), 16);
+
+        overlay.clear();
+        assert_eq!(overlay.live(&counters).count(), 0);
+    }
+
+    #[test]
+    fn evicts_least_hit() {
+        let counters = Counters::<4>::new();
+        let mut overlay = SparseOverlay::<u32, 4>::new();
+        for key in 0..4 {
+            let i = overlay.increment(&counters, key);
+            for _ in 0..key {
+                counters[i].increment();
+            }
+        }
+        // Key 0 has the smallest count, so it is the one evicted.
+        let i = overlay.slot(&0).unwrap();
+        assert_eq!(overlay.increment(&counters, 4), i);
+        assert_eq!(overlay.slot(&0), None);
+        assert_eq!(overlay.key(i), Some(&4));
+        assert_eq!(counters[i].get(), 1);
+    }
+}
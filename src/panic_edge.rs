@@ -0,0 +1,53 @@
+use crate::Counters;
+use alloc::boxed::Box;
+
+impl<const N: usize> Counters<N> {
+    /// Increment the counter at `index` whenever any thread panics.
+    ///
+    /// This installs a panic hook, so that reaching a panic is reported to the
+    /// `SanitizerCoverage` consumer as an edge of its own, distinct from the
+    /// crash that may follow. The consumer then treats the first input that
+    /// reaches a panic as new coverage, even if the panic is caught, or the
+    /// process aborts before the crash itself is recorded.
+    ///
+    /// The new hook increments the counter first, and then calls whichever
+    /// hook was installed before it, by default the one that prints the panic
+    /// message, so existing hooks keep working. Calling this more than once
+    /// chains the hooks, incrementing every edge that was installed. A hook
+    /// that is installed later with `std::panic::set_hook` replaces this one,
+    /// unless it likewise chains to the hook it got from
+    /// `std::panic::take_hook`; so install the panic edge after any other
+    /// hooks, or chain them.
+    ///
+    /// Requires the `std` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= N`, before installing anything.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<4096> = Counters::new();
+    /// const PANIC_EDGE: usize = 4095;
+    ///
+    /// COUNTERS.register();
+    /// COUNTERS.install_panic_edge(PANIC_EDGE);
+    ///
+    /// let _ = std::panic::catch_unwind(|| panic!("reached"));
+    /// assert_eq!(COUNTERS[PANIC_EDGE].get(), 1);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    pub fn install_panic_edge(&'static self, index: usize) {
+        let counter = &self[index];
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            counter.increment();
+            previous(info);
+        }));
+    }
+}
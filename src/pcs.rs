@@ -0,0 +1,133 @@
+#[cfg_attr(sancov_disabled, allow(unused_imports))]
+use crate::sys;
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// The PC table flag marking an entry as a function entry block.
+pub const PC_FLAG_FUNCTION_ENTRY: usize = 1;
+
+/// A table of `N` PCs.
+///
+/// This is the manual equivalent of the table that clang emits for
+/// `-fsanitize-coverage=pc-table`. Each entry is a `(pc, flags)` pair of
+/// `usize`s, and the `i`th entry describes the `i`th counter of a parallel
+/// [`Counters<N>`][crate::Counters]. That is, entry indices must correspond
+/// 1:1 with counter indices, and the two must have the same `N`.
+///
+/// PC tables must be registered by calling the
+/// [`register`][crate::PcTable::register] method, after the parallel counters
+/// have been registered, or together with them via
+/// [`Counters::register_with`][crate::Counters::register_with].
+///
+/// `PcTable<N>` has the same representation as `[[usize; 2]; N]`, which is the
+/// layout `SanitizerCoverage` expects.
+///
+/// # Example
+///
+/// ```
+/// use sancov::{Counters, PcTable};
+///
+/// static COUNTERS: Counters<4096> = Counters::new();
+/// static PCS: PcTable<4096> = PcTable::new();
+///
+/// // Describe the code that counter 42 corresponds to.
+/// PCS.set(42, 0x1234, true);
+///
+/// COUNTERS.register();
+/// PCS.register();
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_pcs_init(_: *const usize, _: *const usize) {}
+/// ```
+#[repr(transparent)]
+pub struct PcTable<const N: usize>(UnsafeCell<[[usize; 2]; N]>);
+
+unsafe impl<const N: usize> Send for PcTable<N> {}
+unsafe impl<const N: usize> Sync for PcTable<N> {}
+
+impl<const N: usize> PcTable<N> {
+    /// Construct a new PC table with `N` zeroed entries.
+    ///
+    /// # Compile-time errors
+    ///
+    /// `N` must not be zero.
+    ///
+    /// ```compile_fail
+    /// use sancov::PcTable;
+    ///
+    /// // This will not compile!
+    /// let _ = PcTable::<0>::new();
+    /// ```
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        const { assert!(N != 0, "`N` must not be zero") };
+        PcTable(UnsafeCell::new([[0; 2]; N]))
+    }
+
+    #[inline]
+    fn entries(&self) -> &[[AtomicUsize; 2]; N] {
+        unsafe {
+            let ptr: *mut [[usize; 2]; N] = self.0.get();
+            let ptr: *const [[AtomicUsize; 2]; N] = ptr as _;
+            &*ptr
+        }
+    }
+
+    /// Set the entry at `index` to the given PC.
+    ///
+    /// If `is_function_entry` is true, the entry's flags are set to
+    /// [`PC_FLAG_FUNCTION_ENTRY`][crate::PC_FLAG_FUNCTION_ENTRY], otherwise they
+    /// are set to zero.
+    ///
+    /// Consumers typically only read the PC table at registration time, so
+    /// entries should be set before calling
+    /// [`register`][crate::PcTable::register].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= N`.
+    #[inline]
+    pub fn set(&self, index: usize, pc: usize, is_function_entry: bool) {
+        assert!(index < N);
+        let [entry_pc, entry_flags] = &self.entries()[index];
+        let flags = if is_function_entry {
+            PC_FLAG_FUNCTION_ENTRY
+        } else {
+            0
+        };
+        entry_pc.store(pc, Ordering::Relaxed);
+        entry_flags.store(flags, Ordering::Relaxed);
+    }
+
+    /// Get the `(pc, flags)` entry at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= N`.
+    #[inline]
+    pub fn get(&self, index: usize) -> (usize, usize) {
+        assert!(index < N);
+        let [pc, flags] = &self.entries()[index];
+        (pc.load(Ordering::Relaxed), flags.load(Ordering::Relaxed))
+    }
+
+    /// Register the given PC table with the `SanitizerCoverage` consumer.
+    ///
+    /// The `SanitizerCoverage` API does not provide any method of unregistering
+    /// PC tables, so `&self` must be `'static`.
+    ///
+    /// Consumers associate a PC table with the most recently registered
+    /// counters, so this should be called immediately after registering the
+    /// parallel [`Counters<N>`][crate::Counters].
+    pub fn register(&'static self) {
+        enabled! {
+            unsafe {
+                let start = self.0.get() as *const usize;
+                let end = start.add(2 * N);
+                sys::__sanitizer_cov_pcs_init(start, end);
+            }
+        }
+    }
+}
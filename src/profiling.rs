@@ -0,0 +1,110 @@
+use crate::Counters;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// Exact 64-bit hit counts kept alongside a set of `N` counters.
+///
+/// 8-bit counters wrap around, or saturate, long before a hot edge stops
+/// being hit, which hides the true distribution of hits. A `Profiling<N>`
+/// wraps a [`Counters<N>`][crate::Counters], and every
+/// [`increment`][crate::Profiling::increment] through it bumps both the 8-bit
+/// counter, so that the `SanitizerCoverage` consumer still gets its feedback,
+/// and an exact 64-bit tally that is meant for profiling rather than fuzzing.
+///
+/// The tallies take eight times as much memory as the counters themselves.
+/// Requires the `profiling` feature.
+///
+/// # Example
+///
+/// ```
+/// use sancov::{Counters, Profiling};
+///
+/// static COUNTERS: Counters<16> = Counters::new();
+/// static PROFILE: Profiling<16> = Profiling::new(&COUNTERS);
+///
+/// COUNTERS.register();
+///
+/// for _ in 0..1000 {
+///     PROFILE.increment(3);
+/// }
+/// assert_eq!(PROFILE.exact(3), 1000);
+/// assert_ne!(COUNTERS[3].get(), 0);
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+/// ```
+pub struct Profiling<const N: usize> {
+    counters: &'static Counters<N>,
+    exact: [AtomicU64; N],
+}
+
+impl<const N: usize> Profiling<N> {
+    /// Construct exact tallies for `counters`, all zero.
+    #[inline]
+    pub const fn new(counters: &'static Counters<N>) -> Self {
+        Profiling {
+            counters,
+            exact: [const { AtomicU64::new(0) }; N],
+        }
+    }
+
+    /// Get the counters that these tallies accompany.
+    #[inline]
+    pub fn counters(&self) -> &'static Counters<N> {
+        self.counters
+    }
+
+    /// Increment the counter at `index`, and its exact tally.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= N`.
+    #[inline]
+    #[cfg_attr(sancov_disabled, allow(unused_variables))]
+    pub fn increment(&self, index: usize) {
+        let tally = &self.exact[index];
+        enabled! {
+            self.counters[index].increment();
+            tally.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Get the exact number of times that the counter at `index` has been
+    /// incremented through these tallies since they were last reset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= N`.
+    #[inline]
+    pub fn exact(&self, index: usize) -> u64 {
+        self.exact[index].load(Ordering::Relaxed)
+    }
+
+    /// Reset every exact tally to zero.
+    ///
+    /// This does not reset the counters themselves.
+    pub fn reset(&self) {
+        for tally in &self.exact {
+            tally.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(all(test, not(sancov_disabled)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_past_wraparound() {
+        static COUNTERS: Counters<4> = Counters::new();
+        static PROFILE: Profiling<4> = Profiling::new(&COUNTERS);
+        for _ in 0..600 {
+            PROFILE.increment(1);
+        }
+        assert_eq!(PROFILE.exact(1), 600);
+        assert_eq!(PROFILE.exact(0), 0);
+        assert_eq!(COUNTERS[1].get(), 90);
+        PROFILE.reset();
+        assert_eq!(PROFILE.exact(1), 0);
+        assert_ne!(COUNTERS[1].get(), 0);
+    }
+}
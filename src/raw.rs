@@ -0,0 +1,136 @@
+//! The raw `SanitizerCoverage` interface, re-exported from `sancov-sys`.
+//!
+//! These are the symbols that clang's instrumentation calls, and that a
+//! `SanitizerCoverage` consumer, such as libFuzzer, defines. The safe types and
+//! functions elsewhere in this crate are built on top of them, and should be
+//! preferred. This module is for advanced uses that those do not cover, and
+//! saves depending on `sancov-sys` directly and keeping its version in sync.
+//!
+//! Unless stated otherwise, calling any of these functions requires the
+//! corresponding symbol to be defined at link time, typically by the consumer.
+
+/// Register the 8-bit counters in `start..end`.
+///
+/// # Safety
+///
+/// `start..end` must be a valid, non-empty range of bytes that stays valid
+/// for the rest of the program, since the consumer keeps reading and writing
+/// it. `start` must not be greater than `end`.
+#[doc(inline)]
+pub use sancov_sys::__sanitizer_cov_8bit_counters_init;
+
+/// Register the boolean flags in `start..end`.
+///
+/// # Safety
+///
+/// `start..end` must be a valid, non-empty range of `bool`s that stays valid
+/// for the rest of the program, since the consumer keeps reading and writing
+/// it. `start` must not be greater than `end`.
+#[doc(inline)]
+pub use sancov_sys::__sanitizer_cov_bool_flag_init;
+
+/// Register the PC table in `pcs_beg..pcs_end`.
+///
+/// The table must be parallel to the most recently registered 8-bit counters
+/// or boolean flags: two `usize`s, a PC and a set of flags, per counter.
+///
+/// # Safety
+///
+/// `pcs_beg..pcs_end` must be a valid range of `usize`s that stays valid, and
+/// is not mutated, for the rest of the program. It must contain exactly two
+/// elements per counter of the most recently registered counters.
+#[doc(inline)]
+pub use sancov_sys::__sanitizer_cov_pcs_init;
+
+/// Register the `u32` guards in `start..stop`, for `trace-pc-guard` coverage.
+///
+/// The consumer typically assigns each guard a unique non-zero index.
+///
+/// # Safety
+///
+/// `start..stop` must be a valid range of `u32`s that stays valid for the rest
+/// of the program, and that the consumer may write to.
+#[doc(inline)]
+pub use sancov_sys::__sanitizer_cov_trace_pc_guard_init;
+
+/// Report that the edge guarded by `guard` was executed.
+///
+/// # Safety
+///
+/// `guard` must point to a `u32` within a range that was registered with
+/// [`__sanitizer_cov_trace_pc_guard_init`], and that the consumer may write
+/// to. [`trace_pc_guard`][crate::trace_pc_guard] is the safe equivalent.
+#[doc(inline)]
+pub use sancov_sys::__sanitizer_cov_trace_pc_guard;
+
+/// Report a comparison between two operands of the given width.
+///
+/// # Safety
+///
+/// These have no preconditions beyond the symbol being defined; they are
+/// `unsafe` only because they are foreign functions. The
+/// [`trace_cmp`][crate::trace_cmp] family is the safe equivalent.
+#[doc(inline)]
+pub use sancov_sys::{
+    __sanitizer_cov_trace_cmp1, __sanitizer_cov_trace_cmp2, __sanitizer_cov_trace_cmp4,
+    __sanitizer_cov_trace_cmp8,
+};
+
+/// Report a comparison between two operands of the given width, where `arg1`
+/// is a compile-time constant.
+///
+/// # Safety
+///
+/// These have no preconditions beyond the symbol being defined. The
+/// [`trace_const_cmp`][crate::trace_const_cmp] family is the safe equivalent.
+#[doc(inline)]
+pub use sancov_sys::{
+    __sanitizer_cov_trace_const_cmp1, __sanitizer_cov_trace_const_cmp2,
+    __sanitizer_cov_trace_const_cmp4, __sanitizer_cov_trace_const_cmp8,
+};
+
+/// Report that a `switch` was executed on `val`.
+///
+/// # Safety
+///
+/// `cases` must point to a valid array of `u64`s in clang's layout: the number
+/// of cases `n`, the bit width of `val`, and then `n` sorted case values.
+/// [`trace_switch`][crate::trace_switch] is the safe equivalent.
+#[doc(inline)]
+pub use sancov_sys::__sanitizer_cov_trace_switch;
+
+/// Report the divisor of a 32- or 64-bit division.
+///
+/// # Safety
+///
+/// These have no preconditions beyond the symbol being defined.
+/// [`trace_div4`][crate::trace_div4] and [`trace_div8`][crate::trace_div8] are
+/// the safe equivalents.
+#[doc(inline)]
+pub use sancov_sys::{__sanitizer_cov_trace_div4, __sanitizer_cov_trace_div8};
+
+/// Report an array index used in pointer arithmetic.
+///
+/// # Safety
+///
+/// This has no preconditions beyond the symbol being defined.
+/// [`trace_gep`][crate::trace_gep] is the safe equivalent.
+#[doc(inline)]
+pub use sancov_sys::__sanitizer_cov_trace_gep;
+
+/// Report the target of an indirect call.
+///
+/// # Safety
+///
+/// This has no preconditions beyond the symbol being defined.
+/// [`trace_pc_indir`][crate::trace_pc_indir] is the safe equivalent.
+#[doc(inline)]
+pub use sancov_sys::__sanitizer_cov_trace_pc_indir;
+
+#[doc(inline)]
+pub use sancov_sys::{CountersInit, WeakHookMemcmp, WeakHookStrcmp};
+
+#[doc(inline)]
+pub use sancov_sys::{
+    sancov_cntrs_section, weak_8bit_counters_init, weak_hook_memcmp, weak_hook_strcmp,
+};
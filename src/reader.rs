@@ -0,0 +1,100 @@
+use crate::{Counters, Snapshot};
+
+/// A read-only view of a set of `N` counters.
+///
+/// A `CountersReader` can read counters, but never increment or reset them,
+/// so it can be handed to a monitoring thread, a progress reporter, or a
+/// plugin without any risk of it perturbing the feedback that the counters
+/// provide. Reads are relaxed loads, exactly like those done through a
+/// `&Counters<N>`, and may race with increments on other threads.
+///
+/// Get one with [`Counters::reader`][crate::Counters::reader].
+///
+/// # Example
+///
+/// ```
+/// use sancov::{Counters, CountersReader};
+///
+/// static COUNTERS: Counters<16> = Counters::new();
+/// COUNTERS.register();
+///
+/// fn report(reader: CountersReader<'_, 16>) -> String {
+///     format!("{}/{} counters hit", reader.count_nonzero(), reader.len())
+/// }
+///
+/// COUNTERS[3].increment();
+/// let monitor = std::thread::spawn(|| report(COUNTERS.reader()));
+/// assert_eq!(monitor.join().unwrap(), "1/16 counters hit");
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+/// ```
+#[derive(Clone, Copy)]
+pub struct CountersReader<'a, const N: usize>(&'a Counters<N>);
+
+impl<const N: usize> Counters<N> {
+    /// Get a read-only view of these counters.
+    ///
+    /// See [`CountersReader`][crate::CountersReader] for an example.
+    #[inline]
+    pub fn reader(&self) -> CountersReader<'_, N> {
+        CountersReader(self)
+    }
+}
+
+impl<'a, const N: usize> CountersReader<'a, N> {
+    /// Get the number of counters.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    /// Are there zero counters?
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Get the current value of the counter at `index`, or `None` if it is out
+    /// of bounds.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<u8> {
+        self.0.get(index).map(|c| c.get())
+    }
+
+    /// Iterate over the counters' current values, in index order.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = u8> + 'a {
+        self.0.iter().map(|c| c.get())
+    }
+
+    /// Iterate over the counters' indices and current values.
+    ///
+    /// See [`Counters::iter_indexed`][crate::Counters::iter_indexed].
+    #[inline]
+    pub fn iter_indexed(&self) -> impl Iterator<Item = (usize, u8)> + 'a {
+        self.0.iter_indexed()
+    }
+
+    /// Count the counters that are non-zero.
+    ///
+    /// See [`Counters::count_nonzero`][crate::Counters::count_nonzero].
+    #[inline]
+    pub fn count_nonzero(&self) -> usize {
+        self.0.count_nonzero()
+    }
+
+    /// Take a snapshot of the counters' current values.
+    ///
+    /// See [`Counters::snapshot`][crate::Counters::snapshot].
+    #[inline]
+    pub fn snapshot(&self) -> Snapshot<N> {
+        self.0.snapshot()
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for CountersReader<'_, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(self.0, f)
+    }
+}
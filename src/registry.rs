@@ -0,0 +1,125 @@
+//! A process-wide record of every region of counters that has been registered.
+
+use crate::atomic::{AtomicU8, Ordering};
+use alloc::vec::Vec;
+use std::sync::Mutex;
+
+#[cfg(feature = "register_once")]
+use crate::sys;
+
+/// The `(start, len)` of each registered region, stored as addresses so that
+/// the registry is `Sync`, and whether its counters are atomic.
+static REGIONS: Mutex<Vec<(usize, usize, bool)>> = Mutex::new(Vec::new());
+
+/// Record that the `len` counters starting at `start` have been registered.
+///
+/// Only regions of `atomic` counters are reset by `reset_all`. Recording the
+/// same region more than once has no effect.
+#[cfg(not(feature = "register_once"))]
+pub(crate) fn record(start: *const u8, len: usize, atomic: bool) {
+    let mut regions = REGIONS.lock().unwrap_or_else(|e| e.into_inner());
+    if !regions.iter().any(|r| r.0 == start as usize && r.1 == len) {
+        regions.push((start as usize, len, atomic));
+    }
+}
+
+/// Register the `len` counters starting at `start` by calling `init`, and then
+/// record the region, unless it has already been recorded.
+///
+/// The registry lock is held across `init`, so that when several threads
+/// register the same region concurrently, exactly one of them calls `init`, and
+/// none of them return before it has finished.
+///
+/// # Safety
+///
+/// `start` must be valid for `len` bytes, and it must be sound to call `init`
+/// with the region's bounds.
+#[cfg(feature = "register_once")]
+pub(crate) unsafe fn record_once(
+    start: *const u8,
+    len: usize,
+    atomic: bool,
+    init: sys::CountersInit,
+) {
+    let mut regions = REGIONS.lock().unwrap_or_else(|e| e.into_inner());
+    if !regions.iter().any(|r| r.0 == start as usize && r.1 == len) {
+        init(start, start.add(len));
+        regions.push((start as usize, len, atomic));
+    }
+}
+
+/// Get the `(start, len)` of every region of 8-bit counters that has been
+/// registered through this crate, in registration order.
+///
+/// Requires the `std` feature.
+///
+/// # Example
+///
+/// ```
+/// use sancov::Counters;
+///
+/// static PARSER: Counters<1024> = Counters::new();
+/// static EVAL: Counters<4096> = Counters::new();
+///
+/// PARSER.register();
+/// EVAL.register();
+///
+/// let regions = sancov::registered_regions();
+/// assert!(regions.contains(&(PARSER.as_array().as_ptr().cast(), 1024)));
+/// assert!(regions.contains(&(EVAL.as_array().as_ptr().cast(), 4096)));
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+/// ```
+pub fn registered_regions() -> Vec<(*const u8, usize)> {
+    let regions = REGIONS.lock().unwrap_or_else(|e| e.into_inner());
+    regions
+        .iter()
+        .map(|&(start, len, _)| (start as *const u8, len))
+        .collect()
+}
+
+/// Reset every counter in every region that has been registered through this
+/// crate back to zero.
+///
+/// This is useful for clearing coverage between iterations in harnesses that
+/// compose counters from many crates. Like
+/// [`Counters::reset`][crate::Counters::reset], it is racy, but not unsafe,
+/// with respect to concurrent readers.
+///
+/// Regions registered by [`CountersCell::register`][crate::CountersCell::register]
+/// are not reset, because their counters are not atomic and may be in use on
+/// another thread; reset them from the thread that owns them instead.
+///
+/// Requires the `std` feature.
+///
+/// # Example
+///
+/// ```
+/// use sancov::Counters;
+///
+/// static PARSER: Counters<1024> = Counters::new();
+/// static EVAL: Counters<4096> = Counters::new();
+///
+/// PARSER.register();
+/// EVAL.register();
+///
+/// PARSER[1].increment();
+/// EVAL[2].increment();
+///
+/// sancov::reset_all();
+/// assert_eq!(PARSER.count_nonzero() + EVAL.count_nonzero(), 0);
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+/// ```
+pub fn reset_all() {
+    let regions = REGIONS.lock().unwrap_or_else(|e| e.into_inner());
+    for &(start, len, _) in regions.iter().filter(|r| r.2) {
+        // Safety: every atomic region is `'static`.
+        let counters = unsafe { core::slice::from_raw_parts(start as *const AtomicU8, len) };
+        for c in counters {
+            c.store(0, Ordering::Relaxed);
+        }
+    }
+}
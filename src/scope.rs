@@ -0,0 +1,70 @@
+use crate::{Counters, Snapshot};
+
+/// An RAII guard that reports counters that became non-zero during its
+/// lifetime.
+///
+/// Created by [`Counters::scope`][crate::Counters::scope]. When dropped, calls
+/// its callback with the index of each counter that was zero when the guard was
+/// created, and is non-zero now.
+#[must_use = "coverage is reported when the guard is dropped"]
+pub struct ScopeGuard<'a, const N: usize, F>
+where
+    F: FnMut(usize),
+{
+    counters: &'a Counters<N>,
+    baseline: Snapshot<N>,
+    on_new: F,
+}
+
+impl<const N: usize> Counters<N> {
+    /// Attribute newly-covered counters to a region of code.
+    ///
+    /// Snapshots the counters now, and returns a guard that, when dropped,
+    /// calls `on_new(index)` for each counter that was zero at the time of
+    /// the snapshot and is non-zero at the time of the drop.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<4096> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// let mut new_edges = vec![];
+    /// {
+    ///     let _scope = COUNTERS.scope(|i| new_edges.push(i));
+    ///
+    ///     // Handle one request...
+    ///     COUNTERS[42].increment();
+    /// }
+    /// assert_eq!(new_edges, [42]);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    pub fn scope<F>(&self, on_new: F) -> ScopeGuard<'_, N, F>
+    where
+        F: FnMut(usize),
+    {
+        ScopeGuard {
+            counters: self,
+            baseline: self.snapshot(),
+            on_new,
+        }
+    }
+}
+
+impl<const N: usize, F> Drop for ScopeGuard<'_, N, F>
+where
+    F: FnMut(usize),
+{
+    fn drop(&mut self) {
+        let now = self.counters.snapshot();
+        for (i, before, after) in self.baseline.diff(&now) {
+            if before == 0 && after != 0 {
+                (self.on_new)(i);
+            }
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::Counters;
+use core::sync::atomic::Ordering;
+
+/// The length of the header that precedes the counter values in the
+/// serialized format.
+const HEADER_LEN: usize = 8;
+
+/// The error returned when deserializing counters, or adopting a foreign
+/// buffer as counters, whose length does not match.
+///
+/// See [`Counters::load_from`][crate::Counters::load_from],
+/// [`Snapshot::deserialize`][crate::Snapshot::deserialize], and
+/// [`Counters::from_raw_parts`][crate::Counters::from_raw_parts].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LenMismatch {
+    /// The number of counters that was expected.
+    pub expected: usize,
+    /// The number of counters that was found.
+    pub found: usize,
+}
+
+impl core::fmt::Display for LenMismatch {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "expected {} counters, found {}",
+            self.expected, self.found
+        )
+    }
+}
+
+impl core::error::Error for LenMismatch {}
+
+impl<const N: usize> Counters<N> {
+    /// The length, in bytes, of these counters' serialized form.
+    pub const SERIALIZED_LEN: usize = HEADER_LEN + N;
+
+    /// Serialize the counters' current values into a portable byte format.
+    ///
+    /// The format is the number of counters as a little-endian `u64`,
+    /// followed by each counter's value as a single byte. It is the same on
+    /// every architecture, so maps can be shipped between machines.
+    ///
+    /// Use [`load_from`][crate::Counters::load_from] to restore them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// let worker = Counters::<16>::new();
+    /// worker[3].increment();
+    ///
+    /// let bytes = worker.to_vec();
+    /// assert_eq!(bytes.len(), Counters::<16>::SERIALIZED_LEN);
+    ///
+    /// let coordinator = Counters::<16>::new();
+    /// coordinator.load_from(&bytes).unwrap();
+    /// assert_eq!(coordinator, worker);
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn to_vec(&self) -> alloc::vec::Vec<u8> {
+        let mut bytes = alloc::vec::Vec::with_capacity(Self::SERIALIZED_LEN);
+        bytes.extend_from_slice(&(N as u64).to_le_bytes());
+        bytes.extend(self.atomics().iter().map(|c| c.load(Ordering::Relaxed)));
+        bytes
+    }
+
+    /// Restore the counters' values from bytes produced by `to_vec`.
+    ///
+    /// Returns an error, without modifying any counters, if `bytes` does not
+    /// hold exactly `N` serialized counters.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::{Counters, LenMismatch};
+    ///
+    /// let counters = Counters::<16>::new();
+    ///
+    /// let mut bytes = vec![];
+    /// bytes.extend_from_slice(&8_u64.to_le_bytes());
+    /// bytes.extend_from_slice(&[0; 8]);
+    ///
+    /// assert_eq!(
+    ///     counters.load_from(&bytes),
+    ///     Err(LenMismatch { expected: 16, found: 8 }),
+    /// );
+    /// ```
+    pub fn load_from(&self, bytes: &[u8]) -> Result<(), LenMismatch> {
+        let mismatch = |found| LenMismatch { expected: N, found };
+        if bytes.len() < HEADER_LEN {
+            return Err(mismatch(0));
+        }
+        let (header, values) = bytes.split_at(HEADER_LEN);
+        let len = u64::from_le_bytes(header.try_into().unwrap());
+        if len != N as u64 {
+            return Err(mismatch(usize::try_from(len).unwrap_or(usize::MAX)));
+        }
+        if values.len() != N {
+            return Err(mismatch(values.len()));
+        }
+        for (c, v) in self.atomics().iter().zip(values) {
+            c.store(*v, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+}
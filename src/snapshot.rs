@@ -0,0 +1,192 @@
+use crate::LenMismatch;
+
+/// A point-in-time copy of the values of a [`Counters<N>`][crate::Counters].
+///
+/// Created by [`Counters::snapshot`][crate::Counters::snapshot]. Unlike the live
+/// counters, a snapshot is plain data that is not concurrently modified, so it
+/// can be compared, diffed, hashed, and stored deterministically.
+///
+/// # Example
+///
+/// ```
+/// use sancov::Counters;
+///
+/// static COUNTERS: Counters<4096> = Counters::new();
+/// COUNTERS.register();
+///
+/// let before = COUNTERS.snapshot();
+///
+/// // Run some unit of work...
+/// COUNTERS[42].increment();
+///
+/// let after = COUNTERS.snapshot();
+/// assert_ne!(before, after);
+/// let changed: Vec<_> = before.diff(&after).collect();
+/// assert_eq!(changed, [(42, 0, 1)]);
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Snapshot<const N: usize>(pub(crate) [u8; N]);
+
+impl<const N: usize> core::fmt::Debug for Snapshot<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let nonzero = self.0.iter().filter(|v| **v != 0).count();
+        write!(f, "Snapshot<{N}> {{ nonzero: {nonzero} }}")
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for Snapshot<N> {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<const N: usize> Snapshot<N> {
+    /// Get the snapshotted counter values.
+    #[inline]
+    pub fn as_array(&self) -> &[u8; N] {
+        &self.0
+    }
+
+    /// Iterate over the counters whose values differ between `self` and
+    /// `other`.
+    ///
+    /// Yields `(index, self_value, other_value)` triples in ascending index
+    /// order. Does not allocate.
+    pub fn diff<'a>(
+        &'a self,
+        other: &'a Snapshot<N>,
+    ) -> impl Iterator<Item = (usize, u8, u8)> + 'a {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+            .map(|(i, (a, b))| (i, *a, *b))
+    }
+
+    /// Serialize the snapshot into a self-describing byte format, for long-term
+    /// storage.
+    ///
+    /// The format is:
+    ///
+    /// 1. the magic bytes [`SNAPSHOT_MAGIC`][crate::SNAPSHOT_MAGIC],
+    /// 2. the format version,
+    ///    [`SNAPSHOT_VERSION`][crate::SNAPSHOT_VERSION], as a
+    ///    little-endian `u32`,
+    /// 3. `N` as a little-endian `u64`,
+    /// 4. each counter's value as a single byte.
+    ///
+    /// It is the same on every architecture. Use
+    /// [`deserialize`][crate::Snapshot::deserialize] to restore it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::{Counters, Snapshot};
+    ///
+    /// let counters = Counters::<16>::new();
+    /// counters[3].increment();
+    ///
+    /// let bytes = counters.snapshot().serialize();
+    /// let restored = Snapshot::<16>::deserialize(&bytes).unwrap();
+    /// assert_eq!(restored, counters.snapshot());
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn serialize(&self) -> alloc::vec::Vec<u8> {
+        let mut bytes = alloc::vec::Vec::with_capacity(SNAPSHOT_HEADER_LEN + N);
+        bytes.extend_from_slice(&SNAPSHOT_MAGIC);
+        bytes.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(N as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.0);
+        bytes
+    }
+
+    /// Restore a snapshot from bytes produced by
+    /// [`serialize`][crate::Snapshot::serialize].
+    ///
+    /// Returns an error if the bytes do not start with the expected magic
+    /// bytes, were written by an unsupported version of the format, or do not
+    /// hold exactly `N` counters, so that a corrupt or mismatched map is never
+    /// silently loaded.
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        if bytes.len() < SNAPSHOT_HEADER_LEN {
+            return Err(SnapshotError::Truncated);
+        }
+        let (magic, rest) = bytes.split_at(4);
+        if magic != SNAPSHOT_MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        let (version, rest) = rest.split_at(4);
+        let version = u32::from_le_bytes(version.try_into().unwrap());
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let (len, values) = rest.split_at(8);
+        let len = u64::from_le_bytes(len.try_into().unwrap());
+        let mismatch = |found| SnapshotError::LenMismatch(LenMismatch { expected: N, found });
+        if len != N as u64 {
+            return Err(mismatch(usize::try_from(len).unwrap_or(usize::MAX)));
+        }
+        let values: [u8; N] = values.try_into().map_err(|_| mismatch(values.len()))?;
+        Ok(Snapshot(values))
+    }
+}
+
+/// The length of the header that precedes the counter values in
+/// [`Snapshot::serialize`][crate::Snapshot::serialize]'s format.
+const SNAPSHOT_HEADER_LEN: usize = 16;
+
+/// The magic bytes that start a serialized [`Snapshot`][crate::Snapshot].
+pub const SNAPSHOT_MAGIC: [u8; 4] = *b"SNCV";
+
+/// The current version of the serialized [`Snapshot`][crate::Snapshot] format.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// The error returned by [`Snapshot::deserialize`][crate::Snapshot::deserialize].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The bytes are too short to hold the header.
+    Truncated,
+    /// The bytes do not start with
+    /// [`SNAPSHOT_MAGIC`][crate::SNAPSHOT_MAGIC].
+    BadMagic,
+    /// The bytes were written by an unsupported version of the format.
+    UnsupportedVersion(u32),
+    /// The bytes hold a different number of counters than expected.
+    LenMismatch(LenMismatch),
+}
+
+impl core::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SnapshotError::Truncated => f.write_str("serialized snapshot is truncated"),
+            SnapshotError::BadMagic => f.write_str("not a serialized snapshot"),
+            SnapshotError::UnsupportedVersion(v) => {
+                write!(f, "unsupported serialized snapshot version {v}")
+            }
+            SnapshotError::LenMismatch(_) => {
+                f.write_str("serialized snapshot has the wrong number of counters")
+            }
+        }
+    }
+}
+
+impl core::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            SnapshotError::LenMismatch(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<LenMismatch> for SnapshotError {
+    #[inline]
+    fn from(e: LenMismatch) -> Self {
+        SnapshotError::LenMismatch(e)
+    }
+}
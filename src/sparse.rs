@@ -0,0 +1,121 @@
+use crate::Snapshot;
+
+impl<const N: usize> Snapshot<N> {
+    /// Encode only the non-zero counters of the snapshot, for sending over the
+    /// network.
+    ///
+    /// The encoding is a sequence of entries, one per non-zero counter, in
+    /// ascending order of index. Each entry is:
+    ///
+    /// 1. the counter's index, as an unsigned LEB128 varint: seven bits at a
+    ///    time, least significant group first, with the high bit of each byte
+    ///    set if another byte follows,
+    /// 2. the counter's value, as a single byte.
+    ///
+    /// There is no header, so an all-zero snapshot encodes to no bytes at all.
+    /// Unlike [`serialize`][crate::Snapshot::serialize], the encoding does not
+    /// record `N`; the receiver must already know it. For a map in which few
+    /// counters are non-zero, this is far smaller than the full map. Use
+    /// [`decode_sparse`][crate::Snapshot::decode_sparse] to restore it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::{Counters, Snapshot};
+    ///
+    /// let counters = Counters::<65536>::new();
+    /// counters[3].increment();
+    /// counters[300].increment_by(2);
+    ///
+    /// let bytes = counters.snapshot().encode_sparse();
+    /// assert_eq!(bytes, [3, 1, 0xac, 0x02, 2]);
+    ///
+    /// let restored = Snapshot::<65536>::decode_sparse(&bytes).unwrap();
+    /// assert_eq!(restored, counters.snapshot());
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn encode_sparse(&self) -> alloc::vec::Vec<u8> {
+        let mut bytes = alloc::vec::Vec::new();
+        for (i, &v) in self.0.iter().enumerate() {
+            if v == 0 {
+                continue;
+            }
+            let mut index = i as u64;
+            while index >= 0x80 {
+                bytes.push(index as u8 | 0x80);
+                index >>= 7;
+            }
+            bytes.push(index as u8);
+            bytes.push(v);
+        }
+        bytes
+    }
+
+    /// Restore a snapshot from bytes produced by
+    /// [`encode_sparse`][crate::Snapshot::encode_sparse].
+    ///
+    /// Counters without an entry are zero. Returns an error if the bytes end
+    /// in the middle of an entry, if an index does not fit in 64 bits, or if
+    /// the indices are not strictly ascending and less than `N`, so that a
+    /// corrupt or mismatched map is never silently loaded.
+    pub fn decode_sparse(bytes: &[u8]) -> Result<Self, SparseError> {
+        let mut values = [0; N];
+        let mut bytes = bytes.iter().copied();
+        let mut next = 0;
+        while let Some(first) = bytes.next() {
+            let mut index = (first & 0x7f) as u64;
+            let mut byte = first;
+            let mut shift = 7;
+            while byte & 0x80 != 0 {
+                byte = bytes.next().ok_or(SparseError::Truncated)?;
+                let group = (byte & 0x7f) as u64;
+                if shift >= 64 || group << shift >> shift != group {
+                    return Err(SparseError::BadVarint);
+                }
+                index |= group << shift;
+                shift += 7;
+            }
+            let value = bytes.next().ok_or(SparseError::Truncated)?;
+            if index < next {
+                return Err(SparseError::IndexNotAscending(index));
+            }
+            if index >= N as u64 {
+                return Err(SparseError::IndexOutOfBounds(index));
+            }
+            values[index as usize] = value;
+            next = index + 1;
+        }
+        Ok(Snapshot(values))
+    }
+}
+
+/// The error returned by
+/// [`Snapshot::decode_sparse`][crate::Snapshot::decode_sparse].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SparseError {
+    /// The bytes end in the middle of an entry.
+    Truncated,
+    /// An index's varint does not fit in 64 bits.
+    BadVarint,
+    /// An index is not greater than the index before it.
+    IndexNotAscending(u64),
+    /// An index is not less than `N`.
+    IndexOutOfBounds(u64),
+}
+
+impl core::fmt::Display for SparseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SparseError::Truncated => f.write_str("sparse snapshot is truncated"),
+            SparseError::BadVarint => f.write_str("sparse snapshot index is too large"),
+            SparseError::IndexNotAscending(i) => {
+                write!(f, "sparse snapshot index {i} is out of order")
+            }
+            SparseError::IndexOutOfBounds(i) => {
+                write!(f, "sparse snapshot index {i} is out of bounds")
+            }
+        }
+    }
+}
+
+impl core::error::Error for SparseError {}
@@ -0,0 +1,106 @@
+use crate::Counters;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+const BUCKETS: usize = usize::BITS as usize;
+
+/// Maximum stack depth coverage.
+///
+/// This is a manual equivalent of clang's `-fsanitize-coverage=stack-depth`,
+/// for interpreters and VMs that manage their own stacks. It tracks the
+/// maximum depth passed to [`record`][crate::StackDepth::record], and exposes
+/// it to the `SanitizerCoverage` consumer as 8-bit counters, one per
+/// power-of-two depth: reaching a depth of `2^k` or more for the first time
+/// makes the `k`th counter non-zero, which the consumer sees as new coverage.
+/// This steers the fuzzer towards inputs that recurse ever more deeply.
+///
+/// Like other counters, a `StackDepth` must be registered by calling the
+/// [`register`][crate::StackDepth::register] method.
+///
+/// # Example
+///
+/// ```
+/// use sancov::StackDepth;
+///
+/// static STACK_DEPTH: StackDepth = StackDepth::new();
+/// STACK_DEPTH.register();
+///
+/// struct Vm {
+///     frames: Vec<u32>,
+/// }
+///
+/// impl Vm {
+///     fn push_frame(&mut self, frame: u32) {
+///         self.frames.push(frame);
+///         STACK_DEPTH.record(self.frames.len());
+///     }
+/// }
+///
+/// let mut vm = Vm { frames: vec![] };
+/// for i in 0..5 {
+///     vm.push_frame(i);
+/// }
+/// assert_eq!(STACK_DEPTH.max(), 5);
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+/// ```
+pub struct StackDepth {
+    max: AtomicUsize,
+    counters: Counters<BUCKETS>,
+}
+
+impl StackDepth {
+    /// Construct a new `StackDepth` with a maximum depth of zero.
+    pub const fn new() -> Self {
+        StackDepth {
+            max: AtomicUsize::new(0),
+            counters: Counters::new(),
+        }
+    }
+
+    /// Register the underlying counters with the `SanitizerCoverage` consumer.
+    pub fn register(&'static self) {
+        self.counters.register();
+    }
+
+    /// Record the current stack depth.
+    ///
+    /// This should be called whenever the stack grows, e.g. on every frame
+    /// push, with the new depth. The units are up to the caller; frames and
+    /// bytes both work.
+    #[inline]
+    pub fn record(&self, depth: usize) {
+        if depth <= self.max.load(Ordering::Relaxed) {
+            return;
+        }
+        self.max.fetch_max(depth, Ordering::Relaxed);
+        let bucket = (usize::BITS - depth.leading_zeros() - 1) as usize;
+        self.counters[bucket].saturating_increment();
+    }
+
+    /// Get the maximum depth recorded so far.
+    #[inline]
+    pub fn max(&self) -> usize {
+        self.max.load(Ordering::Relaxed)
+    }
+
+    /// Get the underlying counters.
+    #[inline]
+    pub fn counters(&self) -> &Counters<BUCKETS> {
+        &self.counters
+    }
+
+    /// Reset the maximum depth back to zero, and the underlying counters with
+    /// it.
+    pub fn reset(&self) {
+        self.max.store(0, Ordering::Relaxed);
+        self.counters.reset();
+    }
+}
+
+impl Default for StackDepth {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
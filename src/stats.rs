@@ -0,0 +1,214 @@
+use crate::{load_word, nonzero_bytes, Counters, WORD};
+use core::sync::atomic::Ordering;
+
+impl<const N: usize> Counters<N> {
+    /// Count how many counters hold each possible value.
+    ///
+    /// The `i`th element of the result is the number of counters whose value
+    /// is `i`. This is useful for diagnosing saturation: if most counters are
+    /// stuck at `255`, `N` is probably too small.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<4096> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// COUNTERS[1].increment();
+    /// COUNTERS[2].increment();
+    ///
+    /// let histogram = COUNTERS.histogram();
+    /// assert_eq!(histogram[1], 2);
+    /// assert_eq!(histogram.iter().sum::<usize>(), 4096);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    pub fn histogram(&self) -> [usize; 256] {
+        let mut histogram = [0; 256];
+        for c in self.atomics() {
+            histogram[c.load(Ordering::Relaxed) as usize] += 1;
+        }
+        histogram
+    }
+
+    /// Sum the values of all of the counters.
+    ///
+    /// Since each counter saturates, or wraps around, at `u8::MAX`, this is not
+    /// the exact number of increments, but it is a coarse measure of how much
+    /// work was done, complementing
+    /// [`count_nonzero`][crate::Counters::count_nonzero], which only measures
+    /// breadth. The counters are read a word at a time, with relaxed loads.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<4096> = Counters::new();
+    /// COUNTERS.register();
+    /// assert_eq!(COUNTERS.total(), 0);
+    ///
+    /// COUNTERS[1].increment();
+    /// COUNTERS[1].increment();
+    /// COUNTERS[4095].increment();
+    /// assert_eq!(COUNTERS.total(), 3);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    pub fn total(&self) -> u64 {
+        let mut chunks = self.atomics().chunks_exact(WORD);
+        let mut total = 0;
+        for chunk in &mut chunks {
+            let word = load_word(chunk);
+            if word != 0 {
+                total += word.to_ne_bytes().iter().map(|&b| b as u64).sum::<u64>();
+            }
+        }
+        for c in chunks.remainder() {
+            total += c.load(Ordering::Relaxed) as u64;
+        }
+        total
+    }
+
+    /// Count how many counters are saturated, i.e. at `u8::MAX`.
+    ///
+    /// Many saturated counters mean that hit counts no longer carry much
+    /// information, which suggests that `N` is too small for the workload,
+    /// e.g. with `hash_increment`, or that the counters should be classified
+    /// or reset more often. The counters are compared a word at a time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<16> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// for _ in 0..1000 {
+    ///     COUNTERS[3].saturating_increment();
+    /// }
+    /// COUNTERS[4].increment();
+    /// assert_eq!(COUNTERS.saturated_count(), 1);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    pub fn saturated_count(&self) -> usize {
+        let mut chunks = self.atomics().chunks_exact(WORD);
+        let mut count = 0;
+        for chunk in &mut chunks {
+            // A byte is saturated exactly when its complement is zero.
+            let word = !load_word(chunk);
+            if word != usize::MAX {
+                count += WORD - nonzero_bytes(word);
+            }
+        }
+        count
+            + chunks
+                .remainder()
+                .iter()
+                .filter(|b| b.load(Ordering::Relaxed) == u8::MAX)
+                .count()
+    }
+
+    /// Check whether at least a `threshold` fraction of the counters are
+    /// saturated.
+    ///
+    /// `threshold` is a fraction between `0.0` and `1.0`. This is meant for
+    /// harnesses that warn when the counters are too small for the workload;
+    /// see [`saturated_count`][crate::Counters::saturated_count].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<4> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// COUNTERS[0].increment_by(255);
+    /// assert!(COUNTERS.is_heavily_saturated(0.25));
+    /// assert!(!COUNTERS.is_heavily_saturated(0.5));
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    pub fn is_heavily_saturated(&self, threshold: f32) -> bool {
+        self.saturated_count() as f32 >= threshold * N as f32
+    }
+
+    /// Score how rare the edges hit in these counters are, given how often
+    /// each edge has been hit across all inputs so far.
+    ///
+    /// This is meant for power schedules that favor inputs touching rarely
+    /// exercised edges. For each counter that is non-zero, the score gains
+    /// `2^32 / global_hits[i]`, i.e. `1 / global_hits[i]` in 32.32 fixed
+    /// point, and the total saturates at `u64::MAX`. An edge with
+    /// `global_hits[i] == 0` is weighted as if it had been hit once, so an
+    /// edge that has never been seen before contributes the most, `2^32`.
+    /// Only whether a counter is non-zero matters, not its value.
+    ///
+    /// The computation is integer-only, so it is available in `no_std`
+    /// environments. The counters are read a word at a time, with relaxed
+    /// loads, and all-zero words are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sancov::Counters;
+    ///
+    /// static COUNTERS: Counters<4> = Counters::new();
+    /// COUNTERS.register();
+    ///
+    /// // Edge 0 is common, edge 3 is rare.
+    /// let global_hits = [1000, 0, 0, 2];
+    ///
+    /// COUNTERS[0].increment();
+    /// let common = COUNTERS.rarity_score(&global_hits);
+    ///
+    /// COUNTERS.reset();
+    /// COUNTERS[3].increment();
+    /// let rare = COUNTERS.rarity_score(&global_hits);
+    ///
+    /// assert!(rare > common);
+    /// assert_eq!(rare, (1 << 32) / 2);
+    /// #
+    /// # #[no_mangle]
+    /// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+    /// ```
+    pub fn rarity_score(&self, global_hits: &[u64; N]) -> u64 {
+        fn weight(hits: u64) -> u64 {
+            (1 << 32) / hits.max(1)
+        }
+
+        let mut counter_chunks = self.atomics().chunks_exact(WORD);
+        let mut hit_chunks = global_hits.chunks_exact(WORD);
+        let mut score = 0u64;
+        for (c, h) in (&mut counter_chunks).zip(&mut hit_chunks) {
+            let word = load_word(c);
+            if word == 0 {
+                continue;
+            }
+            for (&b, &hits) in word.to_ne_bytes().iter().zip(h) {
+                if b != 0 {
+                    score = score.saturating_add(weight(hits));
+                }
+            }
+        }
+        for (c, &hits) in counter_chunks
+            .remainder()
+            .iter()
+            .zip(hit_chunks.remainder())
+        {
+            if c.load(Ordering::Relaxed) != 0 {
+                score = score.saturating_add(weight(hits));
+            }
+        }
+        score
+    }
+}
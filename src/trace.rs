@@ -0,0 +1,538 @@
+//! Manual equivalents of clang's `-fsanitize-coverage=trace-*`
+//! instrumentation.
+
+#[cfg_attr(sancov_disabled, allow(unused_imports))]
+use crate::sys;
+#[cfg_attr(sancov_disabled, allow(unused_imports))]
+use core::ffi::{c_int, c_void, CStr};
+
+macro_rules! trace_cmp_fns {
+    ($($cmp:ident, $const_cmp:ident, $sys_cmp:ident, $sys_const_cmp:ident, $ty:ty, $bits:literal;)*) => {
+        $(
+            #[doc = concat!("Report a comparison between two ", $bits, "-bit operands.")]
+            ///
+            /// This is the manual equivalent of what clang emits for
+            /// `-fsanitize-coverage=trace-cmp`, and lets the `SanitizerCoverage`
+            /// consumer observe both operands so that it can try to satisfy the
+            /// comparison.
+            #[inline]
+            #[cfg_attr(sancov_disabled, allow(unused_variables))]
+            pub fn $cmp(a: $ty, b: $ty) {
+                enabled! {
+                    unsafe { sys::$sys_cmp(a, b) }
+                }
+            }
+
+            #[doc = concat!("Report a comparison between two ", $bits, "-bit operands, where `a` is a constant.")]
+            ///
+            /// Like the non-`const` variant, but tells the `SanitizerCoverage`
+            /// consumer that the first operand is a compile-time constant, which
+            /// it can then use as a dictionary entry.
+            #[inline]
+            #[cfg_attr(sancov_disabled, allow(unused_variables))]
+            pub fn $const_cmp(a: $ty, b: $ty) {
+                enabled! {
+                    unsafe { sys::$sys_const_cmp(a, b) }
+                }
+            }
+        )*
+    };
+}
+
+trace_cmp_fns! {
+    trace_cmp1, trace_const_cmp1, __sanitizer_cov_trace_cmp1, __sanitizer_cov_trace_const_cmp1, u8, "8";
+    trace_cmp2, trace_const_cmp2, __sanitizer_cov_trace_cmp2, __sanitizer_cov_trace_const_cmp2, u16, "16";
+    trace_cmp4, trace_const_cmp4, __sanitizer_cov_trace_cmp4, __sanitizer_cov_trace_const_cmp4, u32, "32";
+    trace_cmp8, trace_const_cmp8, __sanitizer_cov_trace_cmp8, __sanitizer_cov_trace_const_cmp8, u64, "64";
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// An integer type whose comparisons can be reported with
+/// [`trace_cmp`][crate::trace_cmp] and
+/// [`trace_const_cmp`][crate::trace_const_cmp].
+///
+/// This trait is sealed: it is implemented for all of the primitive integer
+/// types up to 64 bits wide, and cannot be implemented outside of this crate.
+pub trait TraceCmp: Copy + sealed::Sealed {
+    #[doc(hidden)]
+    fn trace_cmp(a: Self, b: Self);
+
+    #[doc(hidden)]
+    fn trace_const_cmp(a: Self, b: Self);
+}
+
+macro_rules! impl_trace_cmp {
+    ($($ty:ty => $unsigned:ty, $cmp:ident, $const_cmp:ident;)*) => {
+        $(
+            impl sealed::Sealed for $ty {}
+
+            impl TraceCmp for $ty {
+                #[inline]
+                fn trace_cmp(a: Self, b: Self) {
+                    $cmp(a as $unsigned, b as $unsigned)
+                }
+
+                #[inline]
+                fn trace_const_cmp(a: Self, b: Self) {
+                    $const_cmp(a as $unsigned, b as $unsigned)
+                }
+            }
+        )*
+    };
+}
+
+impl_trace_cmp! {
+    u8 => u8, trace_cmp1, trace_const_cmp1;
+    u16 => u16, trace_cmp2, trace_const_cmp2;
+    u32 => u32, trace_cmp4, trace_const_cmp4;
+    u64 => u64, trace_cmp8, trace_const_cmp8;
+    i8 => u8, trace_cmp1, trace_const_cmp1;
+    i16 => u16, trace_cmp2, trace_const_cmp2;
+    i32 => u32, trace_cmp4, trace_const_cmp4;
+    i64 => u64, trace_cmp8, trace_const_cmp8;
+}
+
+#[cfg(target_pointer_width = "32")]
+impl_trace_cmp! {
+    usize => u32, trace_cmp4, trace_const_cmp4;
+    isize => u32, trace_cmp4, trace_const_cmp4;
+}
+
+#[cfg(target_pointer_width = "64")]
+impl_trace_cmp! {
+    usize => u64, trace_cmp8, trace_const_cmp8;
+    isize => u64, trace_cmp8, trace_const_cmp8;
+}
+
+/// Report a comparison between two operands of any integer type up to 64 bits
+/// wide.
+///
+/// This dispatches to [`trace_cmp1`][crate::trace_cmp1],
+/// [`trace_cmp2`][crate::trace_cmp2], [`trace_cmp4`][crate::trace_cmp4], or
+/// [`trace_cmp8`][crate::trace_cmp8] based on the width of `T`, so that code
+/// that is generic over the operand type does not need to match on it.
+///
+/// Signed operands are passed as their two's complement bit pattern at the same
+/// width, without sign extension, which is what clang does for signed
+/// comparisons.
+///
+/// # Example
+///
+/// ```
+/// use sancov::{trace_cmp, TraceCmp};
+///
+/// fn eval_eq<T: TraceCmp + PartialEq>(a: T, b: T) -> bool {
+///     trace_cmp(a, b);
+///     a == b
+/// }
+///
+/// assert!(eval_eq(42u16, 42));
+/// assert!(!eval_eq(-1i64, 1));
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_trace_cmp2(_: u16, _: u16) {}
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_trace_cmp8(a: u64, b: u64) {
+/// #     assert_eq!((a, b), (u64::MAX, 1));
+/// # }
+/// ```
+#[inline]
+pub fn trace_cmp<T: TraceCmp>(a: T, b: T) {
+    T::trace_cmp(a, b)
+}
+
+/// Report a comparison between two operands of any integer type up to 64 bits
+/// wide, where `a` is a constant.
+///
+/// Like [`trace_cmp`][crate::trace_cmp], but dispatches to the `trace_const_cmp`
+/// variant of the matching width.
+#[inline]
+pub fn trace_const_cmp<T: TraceCmp>(a: T, b: T) {
+    T::trace_const_cmp(a, b)
+}
+
+/// The case values of a `switch`, in the layout that
+/// [`trace_switch`][crate::trace_switch] expects.
+///
+/// That layout is the one clang emits for `-fsanitize-coverage=trace-cmp`: the
+/// number of cases, then the bit width of the switched-upon value, followed by
+/// the case values themselves, sorted in ascending order.
+///
+/// # Example
+///
+/// ```
+/// use sancov::SwitchCases;
+///
+/// let cases = SwitchCases::new(8, [3, 1, 2]);
+/// assert_eq!(cases.as_slice(), &[3, 8, 1, 2, 3]);
+/// ```
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwitchCases<const N: usize> {
+    header: [u64; 2],
+    values: [u64; N],
+}
+
+impl<const N: usize> SwitchCases<N> {
+    /// Construct the case array for a `switch` over a `bits`-wide value with
+    /// the given case values.
+    ///
+    /// The case values do not need to be sorted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is not one of 8, 16, 32, or 64.
+    pub fn new(bits: u64, mut values: [u64; N]) -> Self {
+        assert!(
+            matches!(bits, 8 | 16 | 32 | 64),
+            "switch bit width must be 8, 16, 32, or 64"
+        );
+        values.sort_unstable();
+        SwitchCases {
+            header: [N as u64, bits],
+            values,
+        }
+    }
+
+    /// Get the case array as a slice of `N + 2` elements.
+    #[inline]
+    pub fn as_slice(&self) -> &[u64] {
+        unsafe { core::slice::from_raw_parts(self as *const Self as *const u64, N + 2) }
+    }
+}
+
+/// Report that a `switch` was executed on `val`.
+///
+/// This is the manual equivalent of what clang emits for
+/// `-fsanitize-coverage=trace-cmp` on `switch` statements, and lets the
+/// `SanitizerCoverage` consumer observe which case was taken and how close
+/// `val` was to the other cases.
+///
+/// `cases` must be in clang's layout: the number of cases, then the bit width
+/// of `val`, followed by the sorted case values. Use
+/// [`SwitchCases`][crate::SwitchCases] to construct it.
+///
+/// # Panics
+///
+/// Panics if `cases` is not in the layout described above, or if its bit width
+/// element is not one of 8, 16, 32, or 64.
+///
+/// # Example
+///
+/// ```
+/// use sancov::{trace_switch, SwitchCases};
+///
+/// let opcode: u8 = 0x42;
+///
+/// let cases = SwitchCases::new(8, [0x00, 0x01, 0x42]);
+/// trace_switch(opcode as u64, cases.as_slice());
+/// match opcode {
+///     0x00 => { /* ... */ }
+///     0x01 => { /* ... */ }
+///     0x42 => { /* ... */ }
+///     _ => { /* ... */ }
+/// }
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_trace_switch(_: u64, _: *const u64) {}
+/// ```
+#[inline]
+#[cfg_attr(sancov_disabled, allow(unused_variables))]
+pub fn trace_switch(val: u64, cases: &[u64]) {
+    assert!(cases.len() >= 2, "switch cases are missing their header");
+    assert_eq!(
+        cases[0],
+        cases.len() as u64 - 2,
+        "switch cases length does not match their header"
+    );
+    assert!(
+        matches!(cases[1], 8 | 16 | 32 | 64),
+        "switch bit width must be 8, 16, 32, or 64"
+    );
+    enabled! {
+        unsafe { sys::__sanitizer_cov_trace_switch(val, cases.as_ptr()) }
+    }
+}
+
+/// Report that the edge guarded by `guard` was executed.
+///
+/// This is the manual equivalent of the call that clang emits on every edge
+/// for `-fsanitize-coverage=trace-pc-guard`, and lets interpreter code fire an
+/// edge by passing its guard. The guard holds whatever the `SanitizerCoverage`
+/// consumer stored in it when the [`Guards`][crate::Guards] were registered,
+/// typically a unique index, which the consumer uses to find its own
+/// bookkeeping for the edge. The consumer also sees the caller's PC.
+///
+/// The consumer may write to the guard. In particular, consumers that only
+/// care about whether an edge was hit, not how often, commonly zero the guard
+/// on the first hit and ignore guards that are zero, making the edge
+/// "once"-only.
+///
+/// # Example
+///
+/// ```
+/// use sancov::{trace_pc_guard, Guards};
+///
+/// static GUARDS: Guards<4096> = Guards::new();
+/// GUARDS.register();
+///
+/// fn on_branch(taken: bool) {
+///     trace_pc_guard(&GUARDS[taken as usize]);
+/// }
+///
+/// on_branch(true);
+/// assert_eq!(GUARDS[1].get(), 0);
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_trace_pc_guard_init(_: *mut u32, _: *mut u32) {}
+/// # #[no_mangle]
+/// # pub unsafe fn __sanitizer_cov_trace_pc_guard(guard: *mut u32) {
+/// #     // A "once" consumer.
+/// #     unsafe { *guard = 0 };
+/// # }
+/// ```
+#[cfg(target_has_atomic = "32")]
+#[inline]
+#[cfg_attr(sancov_disabled, allow(unused_variables))]
+pub fn trace_pc_guard(guard: &crate::Guard) {
+    enabled! {
+        unsafe { sys::__sanitizer_cov_trace_pc_guard(guard.as_ptr()) }
+    }
+}
+
+/// Report the divisor of a 32-bit division.
+///
+/// This is the manual equivalent of what clang emits for
+/// `-fsanitize-coverage=trace-div`, and lets the `SanitizerCoverage` consumer
+/// steer towards interesting divisors, such as zero.
+#[inline]
+#[cfg_attr(sancov_disabled, allow(unused_variables))]
+pub fn trace_div4(val: u32) {
+    enabled! {
+        unsafe { sys::__sanitizer_cov_trace_div4(val) }
+    }
+}
+
+/// Report the divisor of a 64-bit division.
+///
+/// This is the manual equivalent of what clang emits for
+/// `-fsanitize-coverage=trace-div`, and lets the `SanitizerCoverage` consumer
+/// steer towards interesting divisors, such as zero.
+///
+/// # Example
+///
+/// ```
+/// use sancov::trace_div8;
+///
+/// fn checked_div(a: u64, b: u64) -> Option<u64> {
+///     trace_div8(b);
+///     a.checked_div(b)
+/// }
+/// # assert_eq!(checked_div(6, 3), Some(2));
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_trace_div8(_: u64) {}
+/// ```
+#[inline]
+#[cfg_attr(sancov_disabled, allow(unused_variables))]
+pub fn trace_div8(val: u64) {
+    enabled! {
+        unsafe { sys::__sanitizer_cov_trace_div8(val) }
+    }
+}
+
+/// Report an array or pointer-arithmetic index.
+///
+/// This is the manual equivalent of what clang emits for
+/// `-fsanitize-coverage=trace-gep`, and lets the `SanitizerCoverage` consumer
+/// steer towards interesting indices, such as ones at or beyond the bounds of a
+/// data structure.
+///
+/// # Example
+///
+/// ```
+/// use sancov::trace_gep;
+///
+/// fn lookup(table: &[u32], idx: usize) -> Option<u32> {
+///     trace_gep(idx);
+///     table.get(idx).copied()
+/// }
+/// # assert_eq!(lookup(&[1, 2, 3], 1), Some(2));
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_trace_gep(_: usize) {}
+/// ```
+#[inline]
+#[cfg_attr(sancov_disabled, allow(unused_variables))]
+pub fn trace_gep(idx: usize) {
+    enabled! {
+        unsafe { sys::__sanitizer_cov_trace_gep(idx) }
+    }
+}
+
+/// Report an indirect call to `callee`.
+///
+/// This is the manual equivalent of what clang emits for
+/// `-fsanitize-coverage=indirect-calls`, and lets the `SanitizerCoverage`
+/// consumer observe indirect call targets. It should be called immediately
+/// before each indirect call, with the address of the function being called.
+///
+/// # Example
+///
+/// ```
+/// use sancov::trace_pc_indir;
+///
+/// fn add(a: u64, b: u64) -> u64 { a + b }
+/// fn sub(a: u64, b: u64) -> u64 { a - b }
+///
+/// static OPS: [fn(u64, u64) -> u64; 2] = [add, sub];
+///
+/// fn dispatch(op: usize, a: u64, b: u64) -> u64 {
+///     let f = OPS[op];
+///     trace_pc_indir(f as usize);
+///     f(a, b)
+/// }
+/// # assert_eq!(dispatch(1, 3, 2), 1);
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_trace_pc_indir(_: usize) {}
+/// ```
+#[inline]
+#[cfg_attr(sancov_disabled, allow(unused_variables))]
+pub fn trace_pc_indir(callee: usize) {
+    enabled! {
+        unsafe { sys::__sanitizer_cov_trace_pc_indir(callee) }
+    }
+}
+
+/// Report a `memcmp`-style comparison of `s1` and `s2` that produced `result`.
+///
+/// This forwards to libFuzzer's `__sanitizer_weak_hook_memcmp`, which lets the
+/// consumer observe the compared bytes so that it can discover magic values.
+/// It is intended for interpreters that implement their own comparison
+/// builtins. The first `min(s1.len(), s2.len())` bytes are reported.
+///
+/// The hook is a weak symbol, and this is a no-op if it is not linked in, or on
+/// targets where weak references are not supported.
+///
+/// `caller_pc` identifies the comparison site. The consumer only uses it to
+/// distinguish comparison sites from one another, so any value that is unique
+/// and stable per site works, for example the address of the builtin that is
+/// performing the comparison, or the address of the bytecode instruction being
+/// executed.
+///
+/// # Example
+///
+/// ```
+/// use sancov::trace_memcmp;
+///
+/// fn builtin_bytes_eq(a: &[u8], b: &[u8]) -> bool {
+///     let eq = a == b;
+///     trace_memcmp(builtin_bytes_eq as usize, a, b, if eq { 0 } else { 1 });
+///     eq
+/// }
+/// # assert!(!builtin_bytes_eq(b"MAGIC", b"MAGIX"));
+/// ```
+#[inline]
+#[cfg_attr(sancov_disabled, allow(unused_variables))]
+pub fn trace_memcmp(caller_pc: usize, s1: &[u8], s2: &[u8], result: i32) {
+    enabled! {
+        if let Some(hook) = sys::weak_hook_memcmp() {
+            let n = s1.len().min(s2.len());
+            unsafe {
+                hook(
+                    caller_pc as *const c_void,
+                    s1.as_ptr().cast(),
+                    s2.as_ptr().cast(),
+                    n,
+                    result as c_int,
+                )
+            }
+        }
+    }
+}
+
+/// Report a `strcmp`-style comparison of `s1` and `s2` that produced `result`.
+///
+/// This forwards to libFuzzer's `__sanitizer_weak_hook_strcmp`. See
+/// [`trace_memcmp`][crate::trace_memcmp] for details, including how to choose
+/// `caller_pc`.
+#[inline]
+#[cfg_attr(sancov_disabled, allow(unused_variables))]
+pub fn trace_strcmp(caller_pc: usize, s1: &CStr, s2: &CStr, result: i32) {
+    enabled! {
+        if let Some(hook) = sys::weak_hook_strcmp() {
+            unsafe {
+                hook(
+                    caller_pc as *const c_void,
+                    s1.as_ptr(),
+                    s2.as_ptr(),
+                    result as c_int,
+                )
+            }
+        }
+    }
+}
+
+/// Report a comparison between two `width`-byte operands, made at the
+/// comparison site identified by `pc`.
+///
+/// The `trace_cmp` callbacks have no PC parameter: the consumer attributes
+/// each comparison to the return address of the callback, which is useless
+/// when every comparison in an interpreter or JIT goes through the same call.
+/// This instead reports the comparison through libFuzzer's
+/// `__sanitizer_weak_hook_memcmp`, which does take the caller's PC, as a
+/// `memcmp` of the operands' native-endian bytes. See
+/// [`trace_memcmp`][crate::trace_memcmp] for how to choose `pc`, and for when
+/// this is a no-op.
+///
+/// `a` and `b` are truncated to their low `width` bytes.
+///
+/// Not every comparison reaches the consumer. libFuzzer's hook ignores
+/// comparisons of fewer than two bytes, so with `width == 1` this does
+/// nothing useful; report those with [`trace_cmp1`][crate::trace_cmp1]
+/// instead. And like `trace_memcmp`, this is always a no-op on targets where
+/// weak references are not supported, which is every non-ELF target,
+/// including macOS and Windows.
+///
+/// # Panics
+///
+/// Panics if `width` is not one of 1, 2, 4, or 8.
+///
+/// # Example
+///
+/// ```
+/// use sancov::trace_cmp_pc;
+///
+/// // An interpreter's `eq` instruction, at bytecode offset `offset`.
+/// fn op_eq32(offset: usize, a: u32, b: u32) -> bool {
+///     trace_cmp_pc(offset, a as u64, b as u64, 4);
+///     a == b
+/// }
+/// # assert!(!op_eq32(0x40, 0xdead_beef, 0));
+/// ```
+#[inline]
+#[cfg_attr(sancov_disabled, allow(unused_variables))]
+pub fn trace_cmp_pc(pc: usize, a: u64, b: u64, width: u8) {
+    assert!(
+        matches!(width, 1 | 2 | 4 | 8),
+        "comparison width must be 1, 2, 4, or 8 bytes"
+    );
+    enabled! {
+        let n = width as usize;
+        let (a, b) = if cfg!(target_endian = "little") {
+            (&a.to_le_bytes()[..n], &b.to_le_bytes()[..n])
+        } else {
+            (&a.to_be_bytes()[8 - n..], &b.to_be_bytes()[8 - n..])
+        };
+        let result = match a.cmp(b) {
+            core::cmp::Ordering::Less => -1,
+            core::cmp::Ordering::Equal => 0,
+            core::cmp::Ordering::Greater => 1,
+        };
+        trace_memcmp(pc, a, b, result);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{Counter, Counters};
+use core::marker::PhantomData;
+
+/// A type whose values map to a dense, stable range of counter indices.
+///
+/// This is typically implemented for a fieldless `enum`, mapping each variant
+/// to its position, so that [`VariantCounters`][crate::VariantCounters] can
+/// keep one counter per variant without hashing. With the `macros` feature,
+/// the `coverage_index!` macro declares such an `enum` and implements this
+/// trait for it.
+///
+/// # Example
+///
+/// ```
+/// use sancov::CoverageIndex;
+///
+/// enum Token {
+///     Ident(String),
+///     Number(u64),
+///     Eof,
+/// }
+///
+/// impl CoverageIndex for Token {
+///     const COUNT: usize = 3;
+///
+///     fn index(&self) -> usize {
+///         match self {
+///             Token::Ident(_) => 0,
+///             Token::Number(_) => 1,
+///             Token::Eof => 2,
+///         }
+///     }
+/// }
+/// ```
+pub trait CoverageIndex {
+    /// The number of distinct indices, i.e. one more than the largest value
+    /// that [`index`][crate::CoverageIndex::index] returns.
+    const COUNT: usize;
+
+    /// Get this value's index, which must be less than
+    /// [`COUNT`][crate::CoverageIndex::COUNT].
+    fn index(&self) -> usize;
+}
+
+/// One counter per variant of `E`.
+///
+/// This is a collision-free, hash-free alternative to calling
+/// [`hash_increment`][crate::Counters::hash_increment] with an `enum`, which is
+/// useful for state-machine coverage: each variant gets its own counter, at the
+/// index given by its [`CoverageIndex`][crate::CoverageIndex] implementation.
+///
+/// `N` must be at least `E::COUNT`, and constructing a `VariantCounters` with
+/// too few counters is a compile-time error, whether or not it initializes a
+/// `static`.
+///
+/// Like other counters, `VariantCounters` must be registered by calling the
+/// [`register`][crate::VariantCounters::register] method.
+///
+/// # Example
+///
+/// ```
+/// use sancov::{CoverageIndex, VariantCounters};
+///
+/// enum State {
+///     Idle,
+///     Running,
+///     Done,
+/// }
+///
+/// impl CoverageIndex for State {
+///     const COUNT: usize = 3;
+///
+///     fn index(&self) -> usize {
+///         match self {
+///             State::Idle => 0,
+///             State::Running => 1,
+///             State::Done => 2,
+///         }
+///     }
+/// }
+///
+/// static STATES: VariantCounters<State, 3> = VariantCounters::new();
+/// STATES.register();
+///
+/// STATES.increment(&State::Running);
+/// assert_eq!(STATES.get(&State::Running), 1);
+/// assert_eq!(STATES.get(&State::Idle), 0);
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+/// ```
+///
+/// Too few counters for the variants is rejected:
+///
+/// ```compile_fail
+/// # use sancov::{CoverageIndex, VariantCounters};
+/// # enum State { Idle, Running, Done }
+/// # impl CoverageIndex for State {
+/// #     const COUNT: usize = 3;
+/// #     fn index(&self) -> usize { 0 }
+/// # }
+/// static STATES: VariantCounters<State, 2> = VariantCounters::new();
+/// ```
+#[repr(transparent)]
+pub struct VariantCounters<E, const N: usize> {
+    counters: Counters<N>,
+    _variants: PhantomData<fn(&E)>,
+}
+
+impl<E: CoverageIndex, const N: usize> VariantCounters<E, N> {
+    /// Construct a new set of counters, one per variant of `E`.
+    ///
+    /// # Compile-time errors
+    ///
+    /// `N` must be at least `E::COUNT`, and must not be zero.
+    pub const fn new() -> Self {
+        const { assert!(N >= E::COUNT, "`N` must cover every variant of `E`") };
+        VariantCounters {
+            counters: Counters::new(),
+            _variants: PhantomData,
+        }
+    }
+
+    /// Register the underlying counters with the `SanitizerCoverage` consumer.
+    pub fn register(&'static self) {
+        self.counters.register();
+    }
+
+    /// Increment the counter for `variant`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `variant.index() >= N`.
+    #[inline]
+    pub fn increment(&self, variant: &E) {
+        self.counter(variant).increment();
+    }
+
+    /// Get the value of the counter for `variant`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `variant.index() >= N`.
+    #[inline]
+    pub fn get(&self, variant: &E) -> u8 {
+        self.counter(variant).get()
+    }
+
+    /// Get the counter for `variant`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `variant.index() >= N`.
+    #[inline]
+    pub fn counter(&self, variant: &E) -> &Counter {
+        &self.counters[variant.index()]
+    }
+
+    /// Get the underlying counters.
+    #[inline]
+    pub fn counters(&self) -> &Counters<N> {
+        &self.counters
+    }
+}
+
+impl<E: CoverageIndex, const N: usize> Default for VariantCounters<E, N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
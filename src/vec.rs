@@ -0,0 +1,171 @@
+use crate::atomic::AtomicU8;
+#[cfg_attr(sancov_disabled, allow(unused_imports))]
+use crate::{sys, Counter};
+use alloc::boxed::Box;
+use core::ops::Index;
+
+/// A heap-allocated collection of counters, whose size is chosen at runtime.
+///
+/// This is like [`Counters<N>`][crate::Counters], but for when the number of
+/// counters isn't known at compile time.
+///
+/// Counters must be registered by calling the
+/// [`register`][crate::CountersVec::register] method, which requires a
+/// `'static` reference because the `SanitizerCoverage` API does not provide any
+/// method of unregistering counters. Use [`leak`][crate::CountersVec::leak] (or
+/// `Box::leak`) to get one. Registered counters are never freed.
+///
+/// # Example
+///
+/// ```
+/// use sancov::CountersVec;
+///
+/// // Choose the number of counters at runtime.
+/// let n = std::env::var("NUM_COUNTERS")
+///     .ok()
+///     .and_then(|n| n.parse().ok())
+///     .unwrap_or(4096);
+///
+/// // Allocate and leak the counters, so that they can be registered.
+/// let counters: &'static CountersVec = CountersVec::new(n).leak();
+/// counters.register();
+///
+/// // Increment a counter.
+/// counters[42].increment();
+/// #
+/// # #[no_mangle]
+/// # pub fn __sanitizer_cov_8bit_counters_init(_: *const u8, _: *const u8) {}
+/// ```
+pub struct CountersVec(Box<[Counter]>);
+
+impl CountersVec {
+    /// Allocate a new set of `n` counters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    ///
+    /// ```should_panic
+    /// use sancov::CountersVec;
+    ///
+    /// // This will panic!
+    /// let _ = CountersVec::new(0);
+    /// ```
+    pub fn new(n: usize) -> Self {
+        assert_ne!(n, 0);
+        CountersVec((0..n).map(|_| Counter(AtomicU8::new(0))).collect())
+    }
+
+    /// Leak these counters, returning a `'static` reference to them, so that
+    /// they may be registered.
+    pub fn leak(self) -> &'static Self {
+        Box::leak(Box::new(self))
+    }
+
+    /// Get the number of counters.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Always returns `false`, since there is always at least one counter.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Get the underlying slice of counters.
+    #[inline]
+    pub fn as_slice(&self) -> &[Counter] {
+        &self.0
+    }
+
+    /// Get the counter at `index`, or `None` if `index >= self.len()`.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&Counter> {
+        self.0.get(index)
+    }
+
+    /// Iterate over the counters.
+    #[inline]
+    pub fn iter(&self) -> core::slice::Iter<'_, Counter> {
+        self.0.iter()
+    }
+
+    /// Register the given counters with the `SanitizerCoverage` consumer.
+    ///
+    /// Repeated registration is idempotent but not necessarily performant.
+    ///
+    /// With the `register_once` feature, the `SanitizerCoverage` consumer is
+    /// only called once for these counters; see
+    /// [`Counters::register`][crate::Counters::register].
+    pub fn register(&'static self) {
+        enabled! {
+            let start = self.0.as_ptr() as *const u8;
+            let len = self.0.len();
+            #[cfg(feature = "register_once")]
+            unsafe {
+                crate::registry::record_once(start, len, true, sys::__sanitizer_cov_8bit_counters_init);
+            }
+            #[cfg(not(feature = "register_once"))]
+            unsafe {
+                sys::__sanitizer_cov_8bit_counters_init(start, start.add(len));
+                #[cfg(feature = "std")]
+                crate::registry::record(start, len, true);
+            }
+        }
+    }
+
+    /// Increment the counter that `x` hashes to.
+    ///
+    /// See [`Counters::hash_increment`][crate::Counters::hash_increment] for
+    /// details.
+    #[inline]
+    #[cfg(feature = "hash_increment")]
+    #[cfg_attr(sancov_disabled, allow(unused_variables))]
+    pub fn hash_increment<T>(&self, x: &T)
+    where
+        T: ?Sized + core::hash::Hash,
+    {
+        enabled! {
+            let i = crate::reduce(fxhash::hash64(x), self.0.len());
+            self[i].increment();
+        }
+    }
+
+    /// Increment the counter that `x` hashes to by `n`.
+    ///
+    /// See [`Counters::hash_increment_by`][crate::Counters::hash_increment_by]
+    /// for details.
+    #[inline]
+    #[cfg(feature = "hash_increment")]
+    #[cfg_attr(sancov_disabled, allow(unused_variables))]
+    pub fn hash_increment_by<T>(&self, x: &T, n: u8)
+    where
+        T: ?Sized + core::hash::Hash,
+    {
+        enabled! {
+            let i = crate::reduce(fxhash::hash64(x), self.0.len());
+            self[i].increment_by(n);
+        }
+    }
+}
+
+impl Index<usize> for CountersVec {
+    type Output = Counter;
+
+    #[inline]
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl<'a> IntoIterator for &'a CountersVec {
+    type Item = &'a Counter;
+    type IntoIter = core::slice::Iter<'a, Counter>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
@@ -0,0 +1,133 @@
+use core::cell::UnsafeCell;
+use core::ops::Index;
+#[cfg(target_has_atomic = "16")]
+use core::sync::atomic::AtomicU16;
+#[cfg(target_has_atomic = "32")]
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::Ordering;
+
+macro_rules! wide_counters {
+    ($counters:ident, $counter:ident, $atomic:ident, $ty:ident, $bits:literal) => {
+        #[doc = concat!("A collection of `N` ", $bits, "-bit counters.")]
+        ///
+        /// This is like [`Counters<N>`][crate::Counters], but with wider
+        /// counters that take longer to saturate, which is useful for
+        /// experimenting with the effect of counter width on feedback quality.
+        ///
+        /// `SanitizerCoverage` only supports 8-bit counters, so these cannot be
+        /// registered with the standard consumer. Instead, read them from your
+        /// own consumer, or hand their address to foreign code via
+        #[doc = concat!("[`as_ptr`][crate::", stringify!($counters), "::as_ptr].")]
+        ///
+        #[doc = concat!("`", stringify!($counters), "<N>` has the same representation as `[", stringify!($ty), "; N]`.")]
+        ///
+        /// # Example
+        ///
+        /// ```
+        #[doc = concat!("use sancov::", stringify!($counters), ";")]
+        ///
+        #[doc = concat!("static COUNTERS: ", stringify!($counters), "<4096> = ", stringify!($counters), "::new();")]
+        ///
+        /// COUNTERS[42].increment();
+        /// assert_eq!(COUNTERS[42].get(), 1);
+        /// ```
+        #[repr(transparent)]
+        pub struct $counters<const N: usize>(UnsafeCell<[$ty; N]>);
+
+        unsafe impl<const N: usize> Send for $counters<N> {}
+        unsafe impl<const N: usize> Sync for $counters<N> {}
+
+        impl<const N: usize> $counters<N> {
+            /// Construct a new set of `N` counters.
+            ///
+            /// # Compile-time errors
+            ///
+            /// `N` must not be zero.
+            pub const fn new() -> Self {
+                const { assert!(N != 0, "`N` must not be zero") };
+                $counters(UnsafeCell::new([0; N]))
+            }
+
+            /// Get the underlying array of counters.
+            #[inline]
+            pub fn as_array(&self) -> &[$counter; N] {
+                unsafe {
+                    let ptr: *mut [$ty; N] = self.0.get();
+                    let ptr: *const [$counter; N] = ptr as _;
+                    &*ptr
+                }
+            }
+
+            /// Get a raw pointer to the first counter.
+            #[inline]
+            pub fn as_ptr(&self) -> *mut $ty {
+                self.0.get() as *mut $ty
+            }
+
+            /// Reset every counter back to zero.
+            pub fn reset(&self) {
+                for c in self.as_array() {
+                    c.0.store(0, Ordering::Relaxed);
+                }
+            }
+        }
+
+        impl<const N: usize> Default for $counters<N> {
+            #[inline]
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<const N: usize> Index<usize> for $counters<N> {
+            type Output = $counter;
+
+            #[inline]
+            fn index(&self, index: usize) -> &Self::Output {
+                assert!(index < N);
+                &self.as_array()[index]
+            }
+        }
+
+        #[doc = concat!("A single ", $bits, "-bit counter.")]
+        ///
+        #[doc = concat!("It has the same representation as a `", stringify!($ty), "`.")]
+        #[repr(transparent)]
+        pub struct $counter($atomic);
+
+        impl $counter {
+            /// Get this counter's current value.
+            #[inline]
+            pub fn get(&self) -> $ty {
+                self.0.load(Ordering::Relaxed)
+            }
+
+            /// Increment this counter.
+            ///
+            /// This uses the same "NeverZero" approach as
+            /// [`Counter::increment`][crate::Counter::increment].
+            #[inline]
+            pub fn increment(&self) {
+                enabled! {
+                    let count = self.0.load(Ordering::Relaxed);
+                    let (count, overflowed) = count.overflowing_add(1);
+                    self.0.store(count + (overflowed as $ty), Ordering::Relaxed);
+                }
+            }
+
+            #[doc = concat!("Increment this counter, saturating at `", stringify!($ty), "::MAX`.")]
+            #[inline]
+            pub fn saturating_increment(&self) {
+                enabled! {
+                    let count = self.0.load(Ordering::Relaxed);
+                    self.0.store(count.saturating_add(1), Ordering::Relaxed);
+                }
+            }
+        }
+    };
+}
+
+#[cfg(target_has_atomic = "16")]
+wide_counters!(Counters16, Counter16, AtomicU16, u16, "16");
+#[cfg(target_has_atomic = "32")]
+wide_counters!(Counters32, Counter32, AtomicU32, u32, "32");
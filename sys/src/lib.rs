@@ -2,4 +2,196 @@
 
 extern "C" {
     pub fn __sanitizer_cov_8bit_counters_init(start: *const u8, end: *const u8);
+    pub fn __sanitizer_cov_bool_flag_init(start: *const bool, end: *const bool);
+    pub fn __sanitizer_cov_pcs_init(pcs_beg: *const usize, pcs_end: *const usize);
+    pub fn __sanitizer_cov_trace_pc_guard_init(start: *mut u32, stop: *mut u32);
+    pub fn __sanitizer_cov_trace_pc_guard(guard: *mut u32);
+
+    pub fn __sanitizer_cov_trace_cmp1(arg1: u8, arg2: u8);
+    pub fn __sanitizer_cov_trace_cmp2(arg1: u16, arg2: u16);
+    pub fn __sanitizer_cov_trace_cmp4(arg1: u32, arg2: u32);
+    pub fn __sanitizer_cov_trace_cmp8(arg1: u64, arg2: u64);
+    pub fn __sanitizer_cov_trace_const_cmp1(arg1: u8, arg2: u8);
+    pub fn __sanitizer_cov_trace_const_cmp2(arg1: u16, arg2: u16);
+    pub fn __sanitizer_cov_trace_const_cmp4(arg1: u32, arg2: u32);
+    pub fn __sanitizer_cov_trace_const_cmp8(arg1: u64, arg2: u64);
+    pub fn __sanitizer_cov_trace_switch(val: u64, cases: *const u64);
+
+    pub fn __sanitizer_cov_trace_div4(val: u32);
+    pub fn __sanitizer_cov_trace_div8(val: u64);
+
+    pub fn __sanitizer_cov_trace_gep(idx: usize);
+
+    pub fn __sanitizer_cov_trace_pc_indir(callee: usize);
+}
+
+use core::ffi::{c_char, c_int, c_void};
+use core::ptr::NonNull;
+
+/// The signature of `__sanitizer_weak_hook_memcmp`.
+pub type WeakHookMemcmp = unsafe extern "C" fn(
+    caller_pc: *const c_void,
+    s1: *const c_void,
+    s2: *const c_void,
+    n: usize,
+    result: c_int,
+);
+
+/// The signature of `__sanitizer_weak_hook_strcmp`.
+pub type WeakHookStrcmp = unsafe extern "C" fn(
+    caller_pc: *const c_void,
+    s1: *const c_char,
+    s2: *const c_char,
+    result: c_int,
+);
+
+/// The signature of `__sanitizer_cov_8bit_counters_init`.
+pub type CountersInit = unsafe extern "C" fn(start: *const u8, end: *const u8);
+
+// Some symbols are weak: they are only defined if the consumer (e.g. libFuzzer)
+// is linked in. Stable Rust cannot declare weak imports, so we emit
+// pointer-sized data words that refer to the symbols via weak references
+// instead. The linker resolves an undefined weak reference to zero, which we
+// read back as `None`.
+#[cfg(all(
+    any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly",
+        target_os = "illumos",
+        target_os = "solaris",
+        target_os = "fuchsia",
+    ),
+    any(target_pointer_width = "32", target_pointer_width = "64"),
+))]
+mod weak {
+    use super::*;
+
+    macro_rules! weak_refs {
+        ($( $name:ident: $ty:ty = $ptr:ident -> $sym:ident; )*) => {
+            #[cfg(target_pointer_width = "64")]
+            weak_refs!(@asm ".quad", "3", $( $ptr -> $sym, )*);
+            #[cfg(target_pointer_width = "32")]
+            weak_refs!(@asm ".long", "2", $( $ptr -> $sym, )*);
+
+            extern "C" {
+                $( static $ptr: Option<$ty>; )*
+            }
+
+            $(
+                #[inline]
+                pub fn $name() -> Option<$ty> {
+                    unsafe { $ptr }
+                }
+            )*
+        };
+
+        (@asm $word:literal, $align:literal, $( $ptr:ident -> $sym:ident, )*) => {
+            core::arch::global_asm!(
+                $( concat!(".weak ", stringify!($sym)), )*
+                ".pushsection .data.rel.ro.sancov_sys_weak_refs,\"aw\"",
+                concat!(".p2align ", $align),
+                $(
+                    concat!(".globl ", stringify!($ptr)),
+                    concat!(".hidden ", stringify!($ptr)),
+                    concat!(stringify!($ptr), ":"),
+                    concat!($word, " ", stringify!($sym)),
+                )*
+                ".popsection",
+            );
+        };
+    }
+
+    weak_refs! {
+        counters_init: CountersInit = sancov_sys_weak_8bit_counters_init -> __sanitizer_cov_8bit_counters_init;
+        memcmp: WeakHookMemcmp = sancov_sys_weak_hook_memcmp -> __sanitizer_weak_hook_memcmp;
+        strcmp: WeakHookStrcmp = sancov_sys_weak_hook_strcmp -> __sanitizer_weak_hook_strcmp;
+        cntrs_start: NonNull<u8> = sancov_sys_weak_cntrs_start -> __start___sancov_cntrs;
+        cntrs_stop: NonNull<u8> = sancov_sys_weak_cntrs_stop -> __stop___sancov_cntrs;
+    }
+}
+
+#[cfg(not(all(
+    any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly",
+        target_os = "illumos",
+        target_os = "solaris",
+        target_os = "fuchsia",
+    ),
+    any(target_pointer_width = "32", target_pointer_width = "64"),
+)))]
+mod weak {
+    use super::*;
+
+    #[inline]
+    pub fn counters_init() -> Option<CountersInit> {
+        Some(__sanitizer_cov_8bit_counters_init)
+    }
+
+    #[inline]
+    pub fn memcmp() -> Option<WeakHookMemcmp> {
+        None
+    }
+
+    #[inline]
+    pub fn strcmp() -> Option<WeakHookStrcmp> {
+        None
+    }
+
+    #[inline]
+    pub fn cntrs_start() -> Option<NonNull<u8>> {
+        None
+    }
+
+    #[inline]
+    pub fn cntrs_stop() -> Option<NonNull<u8>> {
+        None
+    }
+}
+
+/// Get `__sanitizer_cov_8bit_counters_init`, if it is linked in.
+///
+/// Unlike the function declared above, this does not require the symbol to be
+/// defined at link time. On targets where weak references are not supported,
+/// this always returns `Some`, and the symbol must be defined.
+#[inline]
+pub fn weak_8bit_counters_init() -> Option<CountersInit> {
+    weak::counters_init()
+}
+
+/// Get `__sanitizer_weak_hook_memcmp`, if it is linked in.
+///
+/// Always returns `None` on targets where weak references are not supported.
+#[inline]
+pub fn weak_hook_memcmp() -> Option<WeakHookMemcmp> {
+    weak::memcmp()
+}
+
+/// Get `__sanitizer_weak_hook_strcmp`, if it is linked in.
+///
+/// Always returns `None` on targets where weak references are not supported.
+#[inline]
+pub fn weak_hook_strcmp() -> Option<WeakHookStrcmp> {
+    weak::strcmp()
+}
+
+/// Get the bounds of the `__sancov_cntrs` section, if it exists.
+///
+/// The linker synthesizes `__start___sancov_cntrs` and `__stop___sancov_cntrs`
+/// when at least one input section is named `__sancov_cntrs`, which is where
+/// `-fsanitize-coverage=inline-8bit-counters` places its counters. Always
+/// returns `None` on targets where weak references are not supported.
+#[inline]
+pub fn sancov_cntrs_section() -> Option<(*const u8, *const u8)> {
+    let start = weak::cntrs_start()?;
+    let stop = weak::cntrs_stop()?;
+    Some((start.as_ptr() as *const u8, stop.as_ptr() as *const u8))
 }